@@ -4,10 +4,12 @@ use iced::widget::{
     button, center, checkbox, column, container, mouse_area, row, scrollable, text, text_input,
     Column,
 };
-use iced::{Element, Length, Size, Theme};
+use iced::{Element, Length, Size, Subscription, Theme};
 use std::collections::HashSet;
 
-use crate::config::Config;
+use crate::config::{parse_entries, Config, DeleteMode, Link};
+use crate::diagnostic::Diagnostic;
+use crate::journal::JournaledStorage;
 use crate::storage::Storage;
 use crate::strings;
 
@@ -69,13 +71,15 @@ struct RowEditState {
 struct ConfirmState {
     title: String,
     message: String,
+    /// Aliases and groups outside the deletion that refer to it
+    affected: Vec<String>,
     action: ConfirmAction,
 }
 
 #[derive(Debug, Clone)]
 enum ConfirmAction {
-    DeleteSingle(ItemKind, String),
-    DeleteBulk(Vec<(ItemKind, String)>),
+    DeleteSingle(String),
+    DeleteBulk(Vec<String>),
 }
 
 /// Right-click context menu state
@@ -125,17 +129,22 @@ enum Message {
     /// Show right-click context menu for a row
     ShowContextMenu(ItemKind, String, Vec<String>),
 
-    ConfirmYes,
+    ConfirmYes(DeleteMode),
     ConfirmNo,
 
     DismissError,
+
+    /// Ctrl+Z / Ctrl+Shift+Z (or Ctrl+Y)
+    Undo,
+    Redo,
 }
 
 // -- App State ---------------------------------------------------------------
 
 struct Links {
-    storage: Box<dyn Storage>,
+    storage: JournaledStorage,
     config: Config,
+    diagnostics: Vec<Diagnostic>,
 
     tab: Tab,
     search: String,
@@ -156,12 +165,14 @@ struct Links {
 }
 
 impl Links {
-    fn new(storage: Box<dyn Storage>) -> (Self, iced::Task<Message>) {
+    fn new(storage: JournaledStorage) -> (Self, iced::Task<Message>) {
         let config = storage.load().unwrap_or_default();
+        let diagnostics = config.validate();
         (
             Self {
                 storage,
                 config,
+                diagnostics,
                 tab: Tab::All,
                 search: String::new(),
                 sort: SortField::Name,
@@ -181,8 +192,21 @@ impl Links {
         )
     }
 
-    fn save(&self) {
-        let _ = self.storage.save(&self.config);
+    /// Record the outcome of a storage operation and refresh the cached config.
+    fn apply(&mut self, result: anyhow::Result<()>) -> bool {
+        let ok = match result {
+            Ok(()) => {
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                false
+            }
+        };
+        self.config = self.storage.load().unwrap_or_default();
+        self.diagnostics = self.config.validate();
+        ok
     }
 
     /// Save the current row edit and clear edit state.
@@ -203,9 +227,9 @@ impl Links {
 
     fn resolve_url<'a>(&'a self, name: &str) -> Option<&'a str> {
         if let Some(target) = self.config.aliases.get(name) {
-            self.config.links.get(target).map(String::as_str)
+            self.config.links.get(target).map(|l| l.url.as_str())
         } else {
-            self.config.links.get(name).map(String::as_str)
+            self.config.links.get(name).map(|l| l.url.as_str())
         }
     }
 
@@ -247,10 +271,11 @@ impl Links {
                 let name = self.add_link_name.trim().to_string();
                 let url = self.add_link_url.trim().to_string();
                 if !name.is_empty() && !url.is_empty() {
-                    self.config.links.insert(name, url);
-                    self.save();
-                    self.add_link_name.clear();
-                    self.add_link_url.clear();
+                    let result = self.storage.put_link(&name, &url);
+                    if self.apply(result) {
+                        self.add_link_name.clear();
+                        self.add_link_url.clear();
+                    }
                 }
             }
             Message::AddAliasName(s) => self.add_alias_name = s,
@@ -259,14 +284,10 @@ impl Links {
                 let alias = self.add_alias_name.trim().to_string();
                 let target = self.add_alias_target.trim().to_string();
                 if !alias.is_empty() && !target.is_empty() {
-                    if !self.config.links.contains_key(&target) {
-                        self.error = Some(strings::err_alias_target_missing(&target));
-                    } else {
-                        self.config.aliases.insert(alias, target);
-                        self.save();
+                    let result = self.storage.put_alias(&alias, &target);
+                    if self.apply(result) {
                         self.add_alias_name.clear();
                         self.add_alias_target.clear();
-                        self.error = None;
                     }
                 }
             }
@@ -274,29 +295,12 @@ impl Links {
             Message::AddGroupEntries(s) => self.add_group_entries = s,
             Message::SubmitGroup => {
                 let name = self.add_group_name.trim().to_string();
-                let raw = self.add_group_entries.trim().to_string();
-                if !name.is_empty() && !raw.is_empty() {
-                    let entries: Vec<String> = raw
-                        .split(',')
-                        .map(|s| s.trim().to_string())
-                        .filter(|s| !s.is_empty())
-                        .collect();
-                    let missing: Vec<&str> = entries
-                        .iter()
-                        .filter(|e| {
-                            !self.config.links.contains_key(e.as_str())
-                                && !self.config.aliases.contains_key(e.as_str())
-                        })
-                        .map(String::as_str)
-                        .collect();
-                    if !missing.is_empty() {
-                        self.error = Some(strings::err_group_entries_missing(&missing));
-                    } else {
-                        self.config.groups.insert(name, entries);
-                        self.save();
+                let entries = parse_entries(&self.add_group_entries);
+                if !name.is_empty() && !entries.is_empty() {
+                    let result = self.storage.put_group(&name, &entries);
+                    if self.apply(result) {
                         self.add_group_name.clear();
                         self.add_group_entries.clear();
-                        self.error = None;
                     }
                 }
             }
@@ -337,17 +341,19 @@ impl Links {
                             format!("{kind_str} \"{n}\"")
                         })
                         .collect();
+                    let names: Vec<String> = items.into_iter().map(|(_, n)| n).collect();
                     self.confirm = Some(ConfirmState {
                         title: format!(
                             "delete {} item{}",
-                            items.len(),
-                            if items.len() > 1 { "s" } else { "" }
+                            names.len(),
+                            if names.len() > 1 { "s" } else { "" }
                         ),
                         message: format!(
-                            "are you sure you want to delete: {}? this cannot be undone.",
+                            "are you sure you want to delete: {}? press ctrl+z to undo.",
                             labels.join(", ")
                         ),
-                        action: ConfirmAction::DeleteBulk(items),
+                        affected: affected_labels(&self.config, &names),
+                        action: ConfirmAction::DeleteBulk(names),
                     });
                 }
             }
@@ -361,9 +367,10 @@ impl Links {
                 self.confirm = Some(ConfirmState {
                     title: format!("delete {kind_str}"),
                     message: format!(
-                        "are you sure you want to delete {kind_str} \"{name}\"? this cannot be undone."
+                        "are you sure you want to delete {kind_str} \"{name}\"? press ctrl+z to undo."
                     ),
-                    action: ConfirmAction::DeleteSingle(kind, name),
+                    affected: affected_labels(&self.config, std::slice::from_ref(&name)),
+                    action: ConfirmAction::DeleteSingle(name),
                 });
             }
             Message::StartRowEdit(kind, original_name, current_name, current_value) => {
@@ -410,26 +417,41 @@ impl Links {
                 self.context_menu = Some(ContextMenuState { kind, name, urls });
             }
 
-            Message::ConfirmYes => {
+            Message::ConfirmYes(mode) => {
                 if let Some(confirm) = self.confirm.take() {
-                    match confirm.action {
-                        ConfirmAction::DeleteSingle(kind, name) => {
-                            self.delete_item(kind, &name);
+                    let result = match confirm.action {
+                        ConfirmAction::DeleteSingle(name) => {
+                            self.storage.delete(&name, mode).map(drop)
                         }
-                        ConfirmAction::DeleteBulk(items) => {
-                            for (kind, name) in items {
-                                self.delete_item(kind, &name);
-                            }
+                        ConfirmAction::DeleteBulk(names) => {
                             self.selected.clear();
+                            self.storage.update(&mut |config| {
+                                for name in &names {
+                                    // An earlier cascade may have removed it already
+                                    if config.contains(name) {
+                                        config.delete(name, mode)?;
+                                    }
+                                }
+                                Ok(())
+                            })
                         }
-                    }
-                    self.save();
+                    };
+                    self.apply(result);
                 }
             }
             Message::ConfirmNo => {
                 self.confirm = None;
             }
 
+            Message::Undo => {
+                let result = self.storage.undo().map(drop);
+                self.apply(result);
+            }
+            Message::Redo => {
+                let result = self.storage.redo().map(drop);
+                self.apply(result);
+            }
+
             Message::DismissError => {
                 self.error = None;
             }
@@ -437,92 +459,24 @@ impl Links {
         iced::Task::none()
     }
 
-    fn delete_item(&mut self, kind: ItemKind, name: &str) {
-        match kind {
-            ItemKind::Link => {
-                self.config.links.remove(name);
-            }
-            ItemKind::Alias => {
-                self.config.aliases.remove(name);
-            }
-            ItemKind::Group => {
-                self.config.groups.remove(name);
-            }
-        }
-    }
-
     fn apply_edit(&mut self, kind: ItemKind, name: &str, field: &str, value: &str) {
-        match (kind, field) {
-            (ItemKind::Link, "name") => {
-                if value != name {
-                    if let Err(e) = self.config.rename_link(name, value) {
-                        self.error = Some(e.to_string());
-                        return;
-                    }
-                }
-            }
-            (ItemKind::Link, "value") => {
-                if let Some(url) = self.config.links.get_mut(name) {
-                    *url = value.to_string();
-                }
-            }
-            (ItemKind::Alias, "name") => {
-                if value != name {
-                    if let Err(e) = self.config.rename_alias(name, value) {
-                        self.error = Some(e.to_string());
-                        return;
-                    }
-                }
-            }
-            (ItemKind::Alias, "value") => {
-                if !self.config.links.contains_key(value) {
-                    self.error = Some(strings::err_alias_target_missing(value));
-                    return;
-                }
-                if let Some(target) = self.config.aliases.get_mut(name) {
-                    *target = value.to_string();
-                }
-            }
-            (ItemKind::Group, "name") => {
-                if value != name {
-                    if let Some(entries) = self.config.groups.remove(name) {
-                        self.config.groups.insert(value.to_string(), entries);
-                    }
-                }
-            }
-            (ItemKind::Group, "value") => {
-                let entries: Vec<String> = value
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect();
-                let missing: Vec<&str> = entries
-                    .iter()
-                    .filter(|e| {
-                        !self.config.links.contains_key(e.as_str())
-                            && !self.config.aliases.contains_key(e.as_str())
-                    })
-                    .map(String::as_str)
-                    .collect();
-                if !missing.is_empty() {
-                    self.error = Some(strings::err_group_entries_missing(&missing));
-                    return;
-                }
-                if let Some(existing) = self.config.groups.get_mut(name) {
-                    *existing = entries;
-                }
-            }
-            _ => {}
-        }
-        self.error = None;
-        self.save();
+        let result = match (kind, field) {
+            (ItemKind::Link, "name") if value != name => self.storage.rename_link(name, value),
+            (ItemKind::Link, "value") => self.storage.put_link(name, value),
+            (ItemKind::Alias, "name") if value != name => self.storage.rename_alias(name, value),
+            (ItemKind::Alias, "value") => self.storage.put_alias(name, value),
+            (ItemKind::Group, "name") if value != name => self.storage.rename_group(name, value),
+            (ItemKind::Group, "value") => self.storage.put_group(name, &parse_entries(value)),
+            _ => Ok(()),
+        };
+        self.apply(result);
     }
 
     fn visible_items(&self) -> Vec<(ItemKind, String)> {
         let mut items = Vec::new();
         if self.tab == Tab::All || self.tab == Tab::Links {
-            for (name, url) in &self.config.links {
-                if self.matches_filter(&format!("{name} {url}")) {
+            for (name, link) in &self.config.links {
+                if self.matches_filter(&format!("{name} {}", link.filter_text())) {
                     items.push((ItemKind::Link, name.clone()));
                 }
             }
@@ -798,19 +752,19 @@ impl Links {
         let mut links: Vec<_> = self.config.links.iter().collect();
         match self.sort {
             SortField::Name => links.sort_by_key(|(k, _)| k.as_str()),
-            SortField::Value => links.sort_by_key(|(_, v)| v.as_str()),
+            SortField::Value => links.sort_by_key(|(_, v)| v.url.as_str()),
         }
 
         let header = self.view_table_header("name", "url");
 
         let mut rows = Column::new().spacing(0);
         let mut visible_count = 0;
-        for (name, url) in &links {
-            if !self.matches_filter(&format!("{name} {url}")) {
+        for (name, link) in &links {
+            if !self.matches_filter(&format!("{name} {}", link.filter_text())) {
                 continue;
             }
             visible_count += 1;
-            rows = rows.push(self.view_link_row(name, url));
+            rows = rows.push(self.view_link_row(name, link));
             rows = rows.push(iced::widget::rule::horizontal(1).style(|_| rule_style()));
         }
 
@@ -952,7 +906,7 @@ impl Links {
                 .config
                 .links
                 .get(name)
-                .map(|u| vec![u.clone()])
+                .map(|l| vec![l.url.clone()])
                 .unwrap_or_default(),
             ItemKind::Alias => self
                 .resolve_url(name)
@@ -960,15 +914,10 @@ impl Links {
                 .unwrap_or_default(),
             ItemKind::Group => self
                 .config
-                .groups
-                .get(name)
-                .map(|entries| {
-                    entries
-                        .iter()
-                        .filter_map(|e| self.resolve_url(e).map(String::from))
-                        .collect()
-                })
-                .unwrap_or_default(),
+                .group_members(name)
+                .into_iter()
+                .filter_map(|e| self.resolve_url(e).map(String::from))
+                .collect(),
         }
     }
 
@@ -979,6 +928,41 @@ impl Links {
             .is_some_and(|c| c.kind == kind && c.name == name)
     }
 
+    /// Mark a row that has problems and list them underneath it.
+    fn flag_issues<'a>(
+        &self,
+        kind: ItemKind,
+        name: &str,
+        row: iced::widget::Row<'a, Message>,
+    ) -> Element<'a, Message> {
+        let section = match kind {
+            ItemKind::Link => "links",
+            ItemKind::Alias => "aliases",
+            ItemKind::Group => "groups",
+        };
+        let messages: Vec<String> = self
+            .diagnostics
+            .iter()
+            .filter(|d| d.is_for(section, name))
+            .map(|d| match &d.suggestion {
+                Some(help) => format!("{d} ({help})"),
+                None => d.to_string(),
+            })
+            .collect();
+        if messages.is_empty() {
+            return row.into();
+        }
+        container(column![
+            row,
+            container(text(messages.join("\n")).size(11).color(colors::RED)).padding([0, 44]),
+        ])
+        .style(|_| container::Style {
+            background: Some(iced::Background::Color(colors::RED_BG)),
+            ..Default::default()
+        })
+        .into()
+    }
+
     /// Check if a row is currently being edited.
     fn is_editing(&self, kind: ItemKind, name: &str) -> bool {
         self.editing
@@ -986,7 +970,8 @@ impl Links {
             .is_some_and(|e| e.kind == kind && e.original_name == name)
     }
 
-    fn view_link_row<'a>(&'a self, name: &'a str, url: &'a str) -> Element<'a, Message> {
+    fn view_link_row<'a>(&'a self, name: &'a str, link: &'a Link) -> Element<'a, Message> {
+        let url = link.url.as_str();
         let is_selected = self.selected.contains(&(ItemKind::Link, name.to_string()));
         let cb = checkbox(is_selected)
             .on_toggle({
@@ -1007,11 +992,21 @@ impl Links {
             .width(Length::Fill)
             .style(|_, status| link_cell_style(status));
 
-        let url_cell = button(text(url).size(13).color(colors::CYAN))
+        let url_button = button(text(url).size(13).color(colors::CYAN))
             .on_press(Message::OpenUrl(url.to_string()))
             .padding([2, 4])
             .width(Length::Fill)
             .style(|_, status| link_cell_style(status));
+        let summary = link.summary();
+        let url_cell: Element<'_, Message> = if summary.is_empty() {
+            url_button.into()
+        } else {
+            column![
+                url_button,
+                container(text(summary).size(11).color(colors::TEXT_DIM)).padding([0, 4]),
+            ]
+            .into()
+        };
 
         let actions = self.view_row_actions_or_context(ItemKind::Link, name, url);
 
@@ -1026,7 +1021,7 @@ impl Links {
         .align_y(iced::Alignment::Center);
 
         let urls = self.resolve_item_urls(ItemKind::Link, name);
-        mouse_area(r)
+        mouse_area(self.flag_issues(ItemKind::Link, name, r))
             .on_right_press(Message::ShowContextMenu(
                 ItemKind::Link,
                 name.to_string(),
@@ -1075,7 +1070,7 @@ impl Links {
         };
 
         // Target is clickable if it resolves to a URL
-        let target_url = self.config.links.get(target).cloned();
+        let target_url = self.config.links.get(target).map(|l| l.url.clone());
         let target_cell: Element<'_, Message> = if let Some(url) = target_url {
             button(text(target).size(13).color(colors::PURPLE_DIM))
                 .on_press(Message::OpenUrl(url))
@@ -1103,7 +1098,7 @@ impl Links {
         .align_y(iced::Alignment::Center);
 
         let urls = self.resolve_item_urls(ItemKind::Alias, alias);
-        mouse_area(r)
+        mouse_area(self.flag_issues(ItemKind::Alias, alias, r))
             .on_right_press(Message::ShowContextMenu(
                 ItemKind::Alias,
                 alias.to_string(),
@@ -1133,10 +1128,7 @@ impl Links {
             );
         }
 
-        let urls: Vec<String> = entries
-            .iter()
-            .filter_map(|e| self.resolve_url(e).map(String::from))
-            .collect();
+        let urls = self.resolve_item_urls(ItemKind::Group, name);
 
         let name_cell: Element<'_, Message> = if !urls.is_empty() {
             button(text(name).size(13).color(colors::PURPLE))
@@ -1197,7 +1189,7 @@ impl Links {
         .align_y(iced::Alignment::Center);
 
         let urls = self.resolve_item_urls(ItemKind::Group, name);
-        mouse_area(r)
+        mouse_area(self.flag_issues(ItemKind::Group, name, r))
             .on_right_press(Message::ShowContextMenu(
                 ItemKind::Group,
                 name.to_string(),
@@ -1321,48 +1313,65 @@ impl Links {
             .padding([6, 16])
             .style(|_, _| default_button_style());
 
-        let confirm_btn = button(text("delete").size(13).color(colors::RED))
-            .on_press(Message::ConfirmYes)
-            .padding([6, 16])
-            .style(|_, _| button::Style {
-                background: Some(iced::Background::Color(colors::RED_BG)),
+        let confirm_btn = |label: &'a str, mode| {
+            button(text(label).size(13).color(colors::RED))
+                .on_press(Message::ConfirmYes(mode))
+                .padding([6, 16])
+                .style(|_, _| button::Style {
+                    background: Some(iced::Background::Color(colors::RED_BG)),
+                    border: iced::Border {
+                        color: colors::RED,
+                        width: 1.0,
+                        radius: 4.0.into(),
+                    },
+                    text_color: colors::RED,
+                    ..Default::default()
+                })
+        };
+
+        let mut actions = row![iced::widget::Space::new().width(Length::Fill), cancel_btn]
+            .spacing(8)
+            .align_y(iced::Alignment::Center);
+        let mut content = column![title, message].spacing(12);
+
+        // Nothing outside the deletion refers to it, so a cascade only
+        // touches what is being deleted
+        if confirm.affected.is_empty() {
+            actions = actions.push(confirm_btn("delete", DeleteMode::Cascade));
+        } else {
+            let affected = confirm
+                .affected
+                .iter()
+                .fold(column![].spacing(2), |col, label| {
+                    col.push(
+                        text(format!("• {label}"))
+                            .size(12)
+                            .color(colors::TEXT_BRIGHT),
+                    )
+                });
+            content = content
+                .push(text("still referred to by:").size(13).color(colors::TEXT))
+                .push(affected);
+            actions = actions
+                .push(confirm_btn("leave references", DeleteMode::Orphan))
+                .push(confirm_btn("remove references", DeleteMode::Cascade));
+        }
+
+        let modal_content = container(content.push(actions))
+            .padding(24)
+            .max_width(400)
+            .style(|_| container::Style {
+                background: Some(iced::Background::Color(iced::Color::from_rgb(
+                    0.08, 0.08, 0.13,
+                ))),
                 border: iced::Border {
-                    color: colors::RED,
+                    color: colors::BORDER,
                     width: 1.0,
-                    radius: 4.0.into(),
+                    radius: 8.0.into(),
                 },
-                text_color: colors::RED,
                 ..Default::default()
             });
 
-        let modal_content = container(
-            column![
-                title,
-                message,
-                row![
-                    iced::widget::Space::new().width(Length::Fill),
-                    cancel_btn,
-                    confirm_btn
-                ]
-                .spacing(8)
-                .align_y(iced::Alignment::Center),
-            ]
-            .spacing(12),
-        )
-        .padding(24)
-        .max_width(400)
-        .style(|_| container::Style {
-            background: Some(iced::Background::Color(iced::Color::from_rgb(
-                0.08, 0.08, 0.13,
-            ))),
-            border: iced::Border {
-                color: colors::BORDER,
-                width: 1.0,
-                radius: 8.0.into(),
-            },
-            ..Default::default()
-        });
-
         center(modal_content)
             .width(Length::Fill)
             .height(Length::Fill)
@@ -1375,6 +1384,28 @@ impl Links {
             .into()
     }
 
+    /// Undo and redo shortcuts, unless a widget (e.g. a text input) handled
+    /// the key press itself.
+    fn subscription(&self) -> Subscription<Message> {
+        use iced::keyboard::{Event, Key};
+
+        iced::event::listen_with(|event, status, _window| {
+            let iced::Event::Keyboard(Event::KeyPressed { key, modifiers, .. }) = event else {
+                return None;
+            };
+            if status == iced::event::Status::Captured || !modifiers.command() {
+                return None;
+            }
+            // Shift turns the logical key into "Z", so match either case
+            match key.as_ref() {
+                Key::Character("z" | "Z") if modifiers.shift() => Some(Message::Redo),
+                Key::Character("z" | "Z") => Some(Message::Undo),
+                Key::Character("y" | "Y") => Some(Message::Redo),
+                _ => None,
+            }
+        })
+    }
+
     fn theme(&self) -> Theme {
         Theme::Dark
     }
@@ -1384,6 +1415,15 @@ impl Links {
     }
 }
 
+/// Labels for what deleting `names` would leave dangling, e.g. `alias 'gh'`.
+fn affected_labels(config: &Config, names: &[String]) -> Vec<String> {
+    config
+        .external_dependents(names)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
 // -- Style helpers -----------------------------------------------------------
 
 fn input_style(status: text_input::Status) -> text_input::Style {
@@ -1559,7 +1599,7 @@ fn load_icon() -> Option<iced::window::Icon> {
     iced::window::icon::from_rgba(rgba, info.width, info.height).ok()
 }
 
-pub fn run(storage: Box<dyn Storage>) -> iced::Result {
+pub fn run(storage: JournaledStorage) -> iced::Result {
     use std::cell::RefCell;
     let storage = RefCell::new(Some(storage));
 
@@ -1580,6 +1620,7 @@ pub fn run(storage: Box<dyn Storage>) -> iced::Result {
         Links::view,
     )
     .title(Links::title)
+    .subscription(Links::subscription)
     .theme(Links::theme)
     .antialiasing(true)
     .window(window_settings)
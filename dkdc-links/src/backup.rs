@@ -0,0 +1,501 @@
+//! Rolling timestamped copies of the TOML config, taken each time a save
+//! replaces it, so an unwanted edit can be rolled back by hand.
+
+use anyhow::{Context, Result};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::{utc, Config};
+use crate::storage::{sidecar, Storage};
+
+/// How many backups are kept per config file, unless [`KEEP_ENV`] says
+/// otherwise.
+pub const KEEP: usize = 10;
+
+/// Environment variable holding how many backups to keep; `0` turns them off.
+pub const KEEP_ENV: &str = "DKDC_LINKS_BACKUPS";
+
+/// How many backups to keep: the number in [`KEEP_ENV`] if it holds one,
+/// otherwise [`KEEP`].
+pub fn keep() -> usize {
+    env::var(KEEP_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(KEEP)
+}
+
+/// Lines of unchanged context shown around each change in a diff.
+const CONTEXT: usize = 3;
+
+/// One saved copy of the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    /// When it was taken, e.g. `20261017-093012-345`; sorts chronologically.
+    pub id: String,
+    pub path: PathBuf,
+}
+
+impl Backup {
+    pub fn read(&self) -> Result<String> {
+        fs::read_to_string(&self.path).with_context(|| format!("Failed to read backup {}", self.id))
+    }
+
+    /// The config this backup holds.
+    pub fn config(&self) -> Result<Config> {
+        toml::from_str(&self.read()?)
+            .with_context(|| format!("Backup {} is not a valid config", self.id))
+    }
+}
+
+/// Replace the config in `storage` with the one in `backup`. This is an
+/// update like any other, so it can be undone, is committed with git
+/// storage, and backs up the current file first.
+pub fn restore(storage: &dyn Storage, backup: &Backup) -> Result<()> {
+    let restored = backup.config()?;
+    storage.update(&mut |config| {
+        *config = restored.clone();
+        Ok(())
+    })
+}
+
+/// Directory holding the backups of `config`, e.g. `config.toml.backups`.
+pub fn dir(config: &Path) -> PathBuf {
+    sidecar(config, ".backups")
+}
+
+/// Backups of `config`, oldest first.
+pub fn list(config: &Path) -> Result<Vec<Backup>> {
+    let dir = dir(config);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", dir.display()));
+        }
+    };
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry.context("Failed to read backup directory")?.path();
+        let id = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".toml"));
+        if let Some(id) = id {
+            backups.push(Backup {
+                id: id.to_string(),
+                path: path.clone(),
+            });
+        }
+    }
+    backups.sort_by(|a, b| order(&a.id).cmp(&order(&b.id)));
+    Ok(backups)
+}
+
+/// The backup with this id, or the only one whose id starts with it.
+pub fn find(config: &Path, id: &str) -> Result<Backup> {
+    let backups = list(config)?;
+    if let Some(backup) = backups.iter().find(|b| b.id == id) {
+        return Ok(backup.clone());
+    }
+    let matches: Vec<&Backup> = backups.iter().filter(|b| b.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [backup] => Ok((*backup).clone()),
+        [] => anyhow::bail!("no backup '{id}'; see `dkdc-links backup list`"),
+        _ => anyhow::bail!(
+            "'{id}' matches {} backups: {}",
+            matches.len(),
+            matches
+                .iter()
+                .map(|b| b.id.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Save `contents` as the newest backup of `config`, then drop all but the
+/// last `keep`. Nothing is saved if the newest backup already matches, or if
+/// `keep` is 0. The backup gets the config's permissions, so it is no easier
+/// to read.
+pub(crate) fn create(config: &Path, contents: &str, keep: usize) -> Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+    let backups = list(config)?;
+    if let Some(newest) = backups.last() {
+        if fs::read_to_string(&newest.path).is_ok_and(|c| c == contents) {
+            return Ok(());
+        }
+    }
+
+    let dir = dir(config);
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(&dir)
+        .context("Failed to create backup directory")?;
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let id = id_at(since_epoch);
+    // Saves in the same millisecond get a counter after the time, counting
+    // on from the newest one so pruning never frees an earlier name
+    let mut n = backups
+        .iter()
+        .filter(|backup| order(&backup.id).0 == id)
+        .map(|backup| order(&backup.id).1 + 1)
+        .max()
+        .unwrap_or(0);
+    let mut file = loop {
+        let name = match n {
+            0 => format!("{id}.toml"),
+            n => format!("{id}-{n}.toml"),
+        };
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        // Private until it takes the config's permissions below
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        match options.open(dir.join(name)) {
+            Ok(file) => break file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e).context("Failed to write backup"),
+        }
+    };
+    if let Ok(metadata) = fs::metadata(config) {
+        file.set_permissions(metadata.permissions())
+            .context("Failed to set backup permissions")?;
+    }
+    file.write_all(contents.as_bytes())
+        .and_then(|()| file.sync_all())
+        .context("Failed to write backup")?;
+
+    let mut backups = list(config)?;
+    let excess = backups.len().saturating_sub(keep);
+    for backup in backups.drain(..excess) {
+        fs::remove_file(&backup.path).context("Failed to remove old backup")?;
+    }
+    Ok(())
+}
+
+/// Sort key for a backup id: its time, then the counter that tells apart
+/// backups taken in the same millisecond.
+fn order(id: &str) -> (&str, usize) {
+    match id.split_at_checked(19) {
+        Some((time, counter)) => match counter.strip_prefix('-').map(str::parse) {
+            Some(Ok(n)) => (time, n),
+            _ => (id, 0),
+        },
+        None => (id, 0),
+    }
+}
+
+/// Backup id for a time, as UTC down to the millisecond.
+fn id_at(since_epoch: Duration) -> String {
+    let datetime = utc(since_epoch.as_secs());
+    let (date, time) = (datetime.date.unwrap(), datetime.time.unwrap());
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        date.year,
+        date.month,
+        date.day,
+        time.hour,
+        time.minute,
+        time.second,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Line diff from `old` to `new` in unified format, without file headers.
+/// Empty when they have the same lines.
+pub fn diff(old: &str, new: &str) -> String {
+    let ops = line_ops(old, new);
+    let is_change = |(op, _): &(char, &str)| *op != ' ';
+    let mut out = String::new();
+
+    let mut next = 0;
+    while let Some(first) = ops[next..].iter().position(is_change).map(|i| i + next) {
+        // Changes close enough to share context go in one hunk
+        let mut last = first;
+        while let Some(i) = ops[last + 1..].iter().position(is_change) {
+            if i >= 2 * CONTEXT {
+                break;
+            }
+            last += i + 1;
+        }
+        let start = first.saturating_sub(CONTEXT);
+        let end = (last + CONTEXT + 1).min(ops.len());
+
+        let count =
+            |range: &[(char, &str)], skip: char| range.iter().filter(|(op, _)| *op != skip).count();
+        let range = |skip| {
+            let len = count(&ops[start..end], skip);
+            let line = count(&ops[..start], skip) + usize::from(len > 0);
+            format!("{line},{len}")
+        };
+        out.push_str(&format!("@@ -{} +{} @@\n", range('+'), range('-')));
+        for (op, line) in &ops[start..end] {
+            out.push(*op);
+            out.push_str(line);
+            out.push('\n');
+        }
+        next = end;
+    }
+    out
+}
+
+/// Every line of `old` and `new` tagged ` ` (in both), `-` (only in `old`)
+/// or `+` (only in `new`), along a shortest edit script.
+fn line_ops<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let mut ops = Vec::with_capacity(old.len().max(new.len()));
+    push_ops(&old, &new, &mut ops);
+    ops
+}
+
+/// Append the ops turning `old` into `new`, splitting the work at a point
+/// on a shortest path so memory stays linear in the input.
+fn push_ops<'a>(old: &[&'a str], new: &[&'a str], ops: &mut Vec<(char, &'a str)>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let suffix = old_rest
+        .iter()
+        .rev()
+        .zip(new_rest.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_rest[..old_rest.len() - suffix];
+    let new_mid = &new_rest[..new_rest.len() - suffix];
+
+    ops.extend(old[..prefix].iter().map(|line| (' ', *line)));
+    if old_mid.is_empty() || new_mid.is_empty() {
+        ops.extend(old_mid.iter().map(|line| ('-', *line)));
+        ops.extend(new_mid.iter().map(|line| ('+', *line)));
+    } else {
+        let (x, y) = middle_snake(old_mid, new_mid);
+        push_ops(&old_mid[..x], &new_mid[..y], ops);
+        push_ops(&old_mid[x..], &new_mid[y..], ops);
+    }
+    ops.extend(
+        old_rest[old_rest.len() - suffix..]
+            .iter()
+            .map(|line| (' ', *line)),
+    );
+}
+
+/// A point about halfway along a shortest edit path from `old` to `new`,
+/// found by searching from both ends until the searches meet (Myers, "An
+/// O(ND) Difference Algorithm and Its Variations", section 4b). Both
+/// sides must be non-empty and differ in their first and last lines, so
+/// each half of the split is strictly smaller.
+fn middle_snake(old: &[&str], new: &[&str]) -> (usize, usize) {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let offset = (n + m + 1) / 2 + 1;
+    // Furthest x reached on each diagonal k = x - y, from the start and
+    // (in reversed coordinates) from the end
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    let mut d = 0;
+    loop {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let (start_x, start_y) = (x, x - k);
+            while x < n && x - k < m && old[x as usize] == new[(x - k) as usize] {
+                x += 1;
+            }
+            forward[at(k)] = x;
+            let reverse_k = delta - k;
+            if delta % 2 != 0 && reverse_k.abs() < d && x + backward[at(reverse_k)] >= n {
+                return (start_x as usize, start_y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            while x < n && x - k < m && old[(n - x - 1) as usize] == new[(m - x + k - 1) as usize] {
+                x += 1;
+            }
+            backward[at(k)] = x;
+            let forward_k = delta - k;
+            if delta % 2 == 0 && forward_k.abs() <= d && forward[at(forward_k)] + x >= n {
+                return ((n - x) as usize, (m - x + k) as usize);
+            }
+        }
+        d += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config_path() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        (dir, path)
+    }
+
+    #[test]
+    fn test_id_sorts_chronologically() {
+        let id = id_at(Duration::from_millis(1_792_229_412_345));
+        assert_eq!(id, "20261017-093012-345");
+        assert!(id_at(Duration::from_millis(1_792_229_412_346)) > id);
+    }
+
+    #[test]
+    fn test_create_skips_duplicates_and_prunes() {
+        let (_dir, path) = config_path();
+        create(&path, "a = 1\n", KEEP).unwrap();
+        create(&path, "a = 1\n", KEEP).unwrap();
+        assert_eq!(list(&path).unwrap().len(), 1);
+
+        for i in 0..KEEP + 2 {
+            create(&path, &format!("a = {i}\n"), KEEP).unwrap();
+        }
+        let backups = list(&path).unwrap();
+        assert_eq!(backups.len(), KEEP);
+        assert_eq!(backups[0].read().unwrap(), "a = 2\n");
+        assert_eq!(
+            backups.last().unwrap().read().unwrap(),
+            format!("a = {}\n", KEEP + 1)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_backups_are_as_private_as_the_config() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dir, path) = config_path();
+        fs::write(&path, "a = 1\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        create(&path, "a = 0\n", KEEP).unwrap();
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir(&path)), 0o700);
+        assert_eq!(mode(&list(&path).unwrap()[0].path), 0o600);
+    }
+
+    #[test]
+    fn test_same_millisecond_backups_keep_their_order() {
+        let (_dir, path) = config_path();
+        fs::create_dir_all(dir(&path)).unwrap();
+        for id in [
+            "20250101-093012-345-10",
+            "20250101-093012-346",
+            "20250101-093012-345-2",
+        ] {
+            fs::write(dir(&path).join(format!("{id}.toml")), "").unwrap();
+        }
+        create(&path, "a = 1\n", KEEP).unwrap();
+        let ids: Vec<_> = list(&path).unwrap().into_iter().map(|b| b.id).collect();
+        assert_eq!(
+            ids[..3],
+            [
+                "20250101-093012-345-2",
+                "20250101-093012-345-10",
+                "20250101-093012-346"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_restore_can_be_undone() {
+        use crate::journal::JournaledStorage;
+        use crate::toml_storage::TomlStorage;
+
+        let (_dir, path) = config_path();
+        let storage = JournaledStorage::new(Box::new(TomlStorage::new(path.clone())));
+        storage.init().unwrap();
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        let backup = list(&path).unwrap().pop().unwrap();
+
+        restore(&storage, &backup).unwrap();
+        assert!(!storage.load().unwrap().links.contains_key("rust"));
+        assert_eq!(storage.undo().unwrap().as_deref(), Some("delete link rust"));
+        assert!(storage.load().unwrap().links.contains_key("rust"));
+    }
+
+    #[test]
+    fn test_find_by_prefix() {
+        let (_dir, path) = config_path();
+        fs::create_dir_all(dir(&path)).unwrap();
+        for id in ["20261017-093012-345", "20261017-101500-000"] {
+            fs::write(dir(&path).join(format!("{id}.toml")), "").unwrap();
+        }
+
+        assert_eq!(
+            find(&path, "20261017-0930").unwrap().id,
+            "20261017-093012-345"
+        );
+        let err = find(&path, "20261017").unwrap_err().to_string();
+        assert!(err.contains("matches 2 backups"));
+        let err = find(&path, "2025").unwrap_err().to_string();
+        assert!(err.contains("no backup '2025'"));
+    }
+
+    #[test]
+    fn test_list_without_backups() {
+        let (_dir, path) = config_path();
+        assert!(list(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = "[links]\na = 1\nb = 2\nc = 3\nd = 4\ne = 5\nf = 6\ng = 7\nh = 8\ni = 9\n";
+        let new =
+            "[links]\na = 1\nb = 20\nc = 3\nd = 4\ne = 5\nf = 6\ng = 7\nh = 8\ni = 9\nj = 10\n";
+        assert_eq!(
+            diff(old, new),
+            "@@ -1,6 +1,6 @@\n [links]\n a = 1\n-b = 2\n+b = 20\n c = 3\n d = 4\n e = 5\n\
+             @@ -8,3 +8,4 @@\n g = 7\n h = 8\n i = 9\n+j = 10\n"
+        );
+        assert_eq!(diff(old, old), "");
+        assert_eq!(diff("", "a\n"), "@@ -0,0 +1,1 @@\n+a\n");
+    }
+
+    #[test]
+    fn test_line_ops_are_minimal() {
+        let cases = [
+            ("a\nb\nc\n", "c\nb\na\n", 4),
+            ("a\nb\nc\na\nb\nb\na\n", "c\nb\na\nb\na\nc\n", 5),
+            ("x\n", "y\n", 2),
+            ("a\nx\nb\ny\nc\n", "a\nb\nc\nz\n", 3),
+        ];
+        for (old, new, edits) in cases {
+            let ops = line_ops(old, new);
+            // Dropping the additions gives back `old`, dropping the removals `new`
+            let side = |skip: char| -> Vec<&str> {
+                ops.iter()
+                    .filter(|(op, _)| *op != skip)
+                    .map(|(_, line)| *line)
+                    .collect()
+            };
+            assert_eq!(side('+'), old.lines().collect::<Vec<_>>());
+            assert_eq!(side('-'), new.lines().collect::<Vec<_>>());
+            assert_eq!(
+                ops.iter().filter(|(o, _)| *o != ' ').count(),
+                edits,
+                "{old:?} -> {new:?}"
+            );
+        }
+    }
+}
@@ -1,10 +1,18 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::config::{edit_config, print_config};
-use crate::open::open_links;
-use crate::storage::Storage;
-use crate::toml_storage::TomlStorage;
+use crate::backup;
+use crate::config::{edit_config, print_config, Config, DeleteMode, Dependent};
+use crate::diagnostic::Severity;
+use crate::doctor;
+use crate::export;
+use crate::import::{self, OnConflict};
+use crate::journal::JournaledStorage;
+use crate::open::{open_links, plan, OpenMode};
+use crate::storage::{self, Storage, Versioned};
+use crate::strings;
 
 #[derive(Parser, Debug)]
 #[command(name = "dkdc-links")]
@@ -15,6 +23,11 @@ pub struct Args {
     #[arg(short, long)]
     pub config: bool,
 
+    /// Storage backend: `toml[:<path>]`, `git[:<path>]`, `layered[:<layer>]` or
+    /// `sqlite[:<path>]`
+    #[arg(long, env = "DKDC_LINKS_STORAGE", value_name = "SPEC")]
+    pub storage: Option<String>,
+
     /// Open the desktop app
     #[cfg(feature = "app")]
     #[arg(short = 'a', long)]
@@ -25,10 +38,619 @@ pub struct Args {
     #[arg(short = 'w', long)]
     pub webapp: bool,
 
-    /// Things to open
+    /// Show what would open, and through which groups and aliases, without
+    /// opening anything
+    #[arg(long, conflicts_with = "print")]
+    pub dry_run: bool,
+
+    /// Print the URLs instead of opening them
+    #[arg(long)]
+    pub print: bool,
+
+    /// How to list the config when nothing is given to open
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    pub format: ListFormat,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Things to open; put them after `--` when one is named like a command
     pub links: Vec<String>,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ListFormat {
+    /// Aligned sections for reading
+    Table,
+    /// Links, aliases and groups keyed by name, as `export --format json`
+    Json,
+    /// The config as TOML
+    Toml,
+    /// One `kind<TAB>name<TAB>value` line per entry
+    Tsv,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[command(flatten)]
+    Edit(EditCommand),
+
+    /// Revert the last change
+    Undo,
+
+    /// Reapply the last undone change
+    Redo,
+
+    /// Show recent changes to the config (git storage only)
+    History {
+        /// How many changes to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Pull and push config changes with the git remote (git storage only)
+    Sync,
+
+    /// Write the config in another format, to stdout or a file
+    Export {
+        /// Output format
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Print the URLs names resolve to, after alias and group expansion,
+    /// without opening them
+    Resolve {
+        /// Links, aliases, groups or tags, as when opening
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ResolveFormat::Lines)]
+        format: ResolveFormat,
+    },
+
+    /// Show which config layer each entry comes from (layered storage only)
+    Origin {
+        /// Only these names
+        names: Vec<String>,
+    },
+
+    /// Inspect or restore automatic backups of the config file
+    #[command(subcommand)]
+    Backup(BackupCommand),
+
+    /// Check the config for problems; exits non-zero on errors or warnings
+    Doctor {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = DoctorFormat::Text)]
+        format: DoctorFormat,
+    },
+}
+
+/// Commands that change the config. Each runs inside one storage update, so
+/// it is saved whole or not at all.
+#[derive(Subcommand, Debug)]
+pub enum EditCommand {
+    /// Add or update a link
+    Add {
+        name: String,
+        url: String,
+
+        /// Short description shown next to the link
+        #[arg(short, long)]
+        description: Option<String>,
+
+        /// Tag the link (repeatable); replaces any existing tags
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+
+    /// Add or update an alias pointing at a link
+    Alias { name: String, target: String },
+
+    /// Remove links, aliases or groups by name; refuses if anything else
+    /// refers to them
+    Rm {
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// Also remove aliases pointing at them and drop them from groups
+        #[arg(long, conflicts_with = "orphan")]
+        cascade: bool,
+
+        /// Remove them anyway, leaving references dangling
+        #[arg(long)]
+        orphan: bool,
+    },
+
+    /// Rename a link, alias or group
+    Mv { old: String, new: String },
+
+    /// Manage groups
+    #[command(subcommand)]
+    Group(GroupCommand),
+
+    /// Import bookmarks exported from a browser, or straight from its profile
+    Import {
+        /// Bookmarks file, or a browser profile directory
+        file: PathBuf,
+
+        /// File format
+        #[arg(long, value_enum, default_value_t = ImportFormat::Netscape)]
+        format: ImportFormat,
+
+        /// Only import this folder, e.g. `Bookmarks Toolbar/Dev`
+        #[arg(long, value_name = "PATH")]
+        folder: Option<String>,
+
+        /// What to do with names already in the config
+        #[arg(long, value_enum, default_value_t = OnConflict::Skip)]
+        on_conflict: OnConflict,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum DoctorFormat {
+    Text,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ResolveFormat {
+    /// One URL per line
+    Lines,
+    /// An array of `{"name", "url"}` objects
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    /// Bookmarks HTML that browsers can import
+    Netscape,
+    /// A page with a list per section and group
+    Markdown,
+    /// One row per link and alias
+    Csv,
+    /// Links, aliases and groups keyed by name
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ImportFormat {
+    /// The bookmarks HTML file browsers export
+    Netscape,
+    /// Firefox's places.sqlite (needs the `sqlite` feature)
+    Firefox,
+    /// The Bookmarks JSON file of Chrome, Edge, Brave and other Chromium browsers
+    Chromium,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GroupCommand {
+    /// Add entries to a group, creating it if needed
+    Add {
+        name: String,
+        #[arg(required = true)]
+        entries: Vec<String>,
+    },
+
+    /// Remove entries from a group, or the whole group if none are given
+    Remove { name: String, entries: Vec<String> },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupCommand {
+    /// List backups, oldest first
+    List,
+
+    /// Replace the config with a backup
+    Restore {
+        /// Backup id, or a unique prefix of one
+        id: String,
+    },
+
+    /// Show what changed in the config since a backup
+    Diff {
+        /// Backup id, or a unique prefix of one
+        id: String,
+    },
+}
+
+/// What an edit did, to print once it has been saved.
+#[derive(Debug, Default)]
+struct Messages {
+    lines: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Messages {
+    fn say(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+    }
+
+    /// Warn that `name` can only be opened after `--`, since a bare word
+    /// of that name runs the command instead.
+    fn check_shadowing(&mut self, name: &str) {
+        if Args::command().find_subcommand(name).is_some() {
+            self.warnings.push(format!(
+                "'{name}' is also a command; open it with `dkdc-links -- {name}`"
+            ));
+        }
+    }
+
+    fn print(&self) {
+        for line in &self.lines {
+            println!("{line}");
+        }
+        for warning in &self.warnings {
+            eprintln!("warning: {warning}");
+        }
+    }
+}
+
+fn run_command(command: &EditCommand, config: &mut Config) -> Result<Messages> {
+    let mut messages = Messages::default();
+    match command {
+        EditCommand::Add {
+            name,
+            url,
+            description,
+            tags,
+        } => {
+            let mut link = config.links.get(name).cloned().unwrap_or_default();
+            link.url = url.clone();
+            if description.is_some() {
+                link.description = description.clone();
+            }
+            if !tags.is_empty() {
+                link.tags = tags.clone();
+            }
+            config.put_link(name, link)?;
+            messages.say(format!("added link {name}"));
+            messages.check_shadowing(name);
+        }
+        EditCommand::Import {
+            file,
+            format,
+            folder,
+            on_conflict,
+        } => {
+            let read = |file: &Path| {
+                fs::read_to_string(file)
+                    .with_context(|| format!("Failed to read {}", file.display()))
+            };
+            let in_profile = |name: &str| {
+                if file.is_dir() {
+                    file.join(name)
+                } else {
+                    file.clone()
+                }
+            };
+            let mut root = match format {
+                ImportFormat::Netscape => import::parse_netscape(&read(file)?)?,
+                ImportFormat::Firefox => import::read_firefox(&in_profile(import::FIREFOX_FILE))?,
+                ImportFormat::Chromium => {
+                    import::parse_chromium(&read(&in_profile(import::CHROMIUM_FILE))?)?
+                }
+            };
+            if let Some(path) = folder {
+                let Some(found) = root.find(path) else {
+                    anyhow::bail!("no folder '{path}' in {}", file.display());
+                };
+                root = import::Folder {
+                    folders: vec![found.clone()],
+                    ..import::Folder::default()
+                };
+            }
+            let report = import::import(config, &root, *on_conflict)?;
+            for conflict in &report.conflicts {
+                messages.say(conflict.to_string());
+            }
+            messages.say(report.summary());
+        }
+        EditCommand::Alias { name, target } => {
+            config.add_alias(name, target)?;
+            messages.say(format!("added alias {name} -> {target}"));
+            messages.check_shadowing(name);
+        }
+        EditCommand::Rm {
+            names,
+            cascade,
+            orphan,
+        } => {
+            if let Some(name) = names.iter().find(|n| !config.contains(n)) {
+                anyhow::bail!("'{name}' not found in [links], [aliases] or [groups]");
+            }
+            let mode = if *cascade {
+                DeleteMode::Cascade
+            } else if *orphan {
+                DeleteMode::Orphan
+            } else {
+                // References among the names being removed don't count, so
+                // once nothing else refers to them a cascade is safe
+                let outside = config.external_dependents(names);
+                for name in names {
+                    let blocking: Vec<Dependent> = config
+                        .dependents(name)
+                        .into_iter()
+                        .filter(|d| outside.contains(d))
+                        .collect();
+                    if !blocking.is_empty() {
+                        anyhow::bail!(
+                            "{}\nuse --cascade or --orphan to remove anyway",
+                            strings::err_has_dependents(name, &blocking)
+                        );
+                    }
+                }
+                DeleteMode::Cascade
+            };
+            for name in names {
+                remove_name(config, name, mode, &mut messages)?;
+            }
+        }
+        EditCommand::Mv { old, new } => {
+            if config.links.contains_key(old) {
+                config.rename_link(old, new)?;
+                messages.say(format!("renamed link {old} -> {new}"));
+            } else if config.aliases.contains_key(old) {
+                config.rename_alias(old, new)?;
+                messages.say(format!("renamed alias {old} -> {new}"));
+            } else if config.groups.contains_key(old) {
+                config.rename_group(old, new)?;
+                messages.say(format!("renamed group {old} -> {new}"));
+            } else {
+                anyhow::bail!("'{old}' not found in [links], [aliases] or [groups]");
+            }
+            messages.check_shadowing(new);
+        }
+        EditCommand::Group(GroupCommand::Add { name, entries }) => {
+            config.add_to_group(name, entries)?;
+            messages.say(format!("updated group {name}"));
+            messages.check_shadowing(name);
+        }
+        EditCommand::Group(GroupCommand::Remove { name, entries }) => {
+            config.remove_from_group(name, entries)?;
+            if entries.is_empty() || config.groups[name].is_empty() {
+                config.delete(name, DeleteMode::Refuse)?;
+                messages.say(format!("removed group {name}"));
+            } else {
+                messages.say(format!("updated group {name}"));
+            }
+        }
+    }
+    Ok(messages)
+}
+
+/// The config file, if the storage keeps one in TOML.
+fn toml_path(storage: &dyn Storage) -> Option<&Path> {
+    storage
+        .path()
+        .filter(|_| matches!(storage.backend_name(), "toml" | "git"))
+}
+
+/// Print what `names` resolve to. Names that don't resolve are reported on
+/// stderr, and make the command fail once the rest are printed.
+fn run_resolve(config: &Config, names: &[String], format: ResolveFormat) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct Resolved<'a> {
+        name: &'a str,
+        url: String,
+    }
+
+    let mut resolved = Vec::new();
+    let mut failed = 0;
+    for target in plan(names, config) {
+        match target.url {
+            Ok(url) => resolved.push(Resolved {
+                name: target.name,
+                url,
+            }),
+            Err(e) => {
+                eprintln!("[dkdc] skipping {}: {e}", target.name);
+                failed += 1;
+            }
+        }
+    }
+
+    match format {
+        ResolveFormat::Lines => {
+            for r in &resolved {
+                println!("{}", r.url);
+            }
+        }
+        ResolveFormat::Json => println!("{}", serde_json::to_string_pretty(&resolved)?),
+    }
+
+    if failed > 0 {
+        anyhow::bail!(
+            "{failed} of {} names did not resolve",
+            failed + resolved.len()
+        );
+    }
+    Ok(())
+}
+
+fn run_doctor(storage: &dyn Storage, format: DoctorFormat) -> Result<()> {
+    let diagnostics = storage.diagnostics()?;
+
+    match format {
+        DoctorFormat::Text => {
+            let path = storage.path();
+            let source = toml_path(storage).and_then(|p| fs::read_to_string(p).ok());
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic.render(path, source.as_deref()));
+            }
+            println!("{}", doctor::summary(&diagnostics));
+        }
+        DoctorFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+        }
+    }
+
+    if diagnostics.iter().any(|d| d.severity > Severity::Info) {
+        anyhow::bail!("doctor found problems: {}", doctor::summary(&diagnostics));
+    }
+    Ok(())
+}
+
+fn remove_name(
+    config: &mut Config,
+    name: &str,
+    mode: DeleteMode,
+    messages: &mut Messages,
+) -> Result<()> {
+    // An earlier cascade may have removed it already
+    if !config.contains(name) {
+        return Ok(());
+    }
+    let kinds: Vec<&str> = [
+        ("link", config.links.contains_key(name)),
+        ("alias", config.aliases.contains_key(name)),
+        ("group", config.groups.contains_key(name)),
+    ]
+    .into_iter()
+    .filter_map(|(kind, found)| found.then_some(kind))
+    .collect();
+
+    let dependents = config.delete(name, mode)?;
+    for kind in kinds {
+        messages.say(format!("removed {kind} {name}"));
+    }
+    for dependent in dependents {
+        match (mode, dependent) {
+            (DeleteMode::Orphan, dependent) => messages
+                .warnings
+                .push(format!("{dependent} still refers to '{name}'")),
+            (_, Dependent::Alias(alias)) => messages.say(format!("removed alias {alias}")),
+            (_, Dependent::Group(group)) => messages.say(format!("updated group {group}")),
+        }
+    }
+    Ok(())
+}
+
+fn run_export(config: &Config, format: ExportFormat, output: Option<&Path>) -> Result<()> {
+    let rendered = match format {
+        ExportFormat::Netscape => export::netscape(config),
+        ExportFormat::Markdown => export::markdown(config),
+        ExportFormat::Csv => export::csv(config),
+        ExportFormat::Json => export::json(config)?,
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, rendered).with_context(|| format!("Failed to write {}", path.display()))
+        }
+        None => {
+            print!("{rendered}");
+            Ok(())
+        }
+    }
+}
+
+fn run_origin(storage: &dyn Storage, names: &[String]) -> Result<()> {
+    let layered = storage.layered().with_context(|| {
+        format!(
+            "{} storage has a single layer; use --storage layered",
+            storage.backend_name()
+        )
+    })?;
+    let origins = layered.origins()?;
+    if let Some(name) = names
+        .iter()
+        .find(|n| !origins.iter().any(|o| o.name == **n))
+    {
+        anyhow::bail!("'{name}' not found in [links], [aliases] or [groups]");
+    }
+    for origin in origins {
+        if !names.is_empty() && !names.contains(&origin.name) {
+            continue;
+        }
+        let mut line = format!(
+            "{} {}: {} ({})",
+            origin.section.kind(),
+            origin.name,
+            origin.layer.name,
+            origin.layer.path.display()
+        );
+        if !origin.overrides.is_empty() {
+            line.push_str(&format!(", overrides {}", origin.overrides.join(", ")));
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn versioned(storage: &dyn Storage) -> Result<&dyn Versioned> {
+    storage.versioned().with_context(|| {
+        format!(
+            "{} storage is not kept in git; use --storage git",
+            storage.backend_name()
+        )
+    })
+}
+
+fn run_undo(storage: &JournaledStorage, redo: bool) -> Result<()> {
+    let (done, verb) = if redo {
+        (storage.redo()?, "redo")
+    } else {
+        (storage.undo()?, "undo")
+    };
+    match done {
+        Some(summary) => println!("{verb}: {summary}"),
+        None => println!("nothing to {verb}"),
+    }
+    Ok(())
+}
+
+fn run_backup(storage: &dyn Storage, command: &BackupCommand) -> Result<()> {
+    let path =
+        toml_path(storage).context("backups only work with the toml or git storage backend")?;
+
+    match command {
+        BackupCommand::List => {
+            let backups = backup::list(path)?;
+            if backups.is_empty() {
+                println!("no backups yet");
+            }
+            for backup in backups {
+                let summary = backup.config().ok().map_or_else(
+                    || "invalid config".to_string(),
+                    |config| {
+                        format!(
+                            "{} links, {} aliases, {} groups",
+                            config.links.len(),
+                            config.aliases.len(),
+                            config.groups.len()
+                        )
+                    },
+                );
+                println!("{}  {summary}", backup.id);
+            }
+        }
+        BackupCommand::Restore { id } => {
+            let backup = backup::find(path, id)?;
+            backup::restore(storage, &backup)?;
+            println!("restored backup {}", backup.id);
+        }
+        BackupCommand::Diff { id } => {
+            let backup = backup::find(path, id)?;
+            let current = fs::read_to_string(path).context("Failed to read config file")?;
+            let diff = backup::diff(&backup.read()?, &current);
+            if diff.is_empty() {
+                println!("no changes since backup {}", backup.id);
+            } else {
+                println!("--- backup {}\n+++ {}", backup.id, path.display());
+                print!("{diff}");
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn run<I, T>(args: I) -> Result<()>
 where
     I: IntoIterator<Item = T>,
@@ -36,35 +658,166 @@ where
 {
     let args = Args::parse_from(args);
 
-    let storage = TomlStorage::with_default_path()?;
+    let storage = storage::open(args.storage.as_deref().unwrap_or("toml"))?;
 
     #[cfg(feature = "app")]
     if args.app {
         storage.init()?;
-        return crate::app::run(Box::new(storage)).map_err(|e| anyhow::anyhow!("{e}"));
+        return crate::app::run(storage).map_err(|e| anyhow::anyhow!("{e}"));
     }
 
     #[cfg(feature = "webapp")]
     if args.webapp {
         storage.init()?;
-        return crate::webapp::run(Box::new(storage));
+        return crate::webapp::run(storage);
     }
     storage.init()?;
 
     if args.config {
-        if let Some(path) = storage.path() {
-            edit_config(path)?;
+        let path = toml_path(&storage)
+            .context("--config only works with the toml or git storage backend")?;
+        return edit_config(path);
+    }
+
+    match &args.command {
+        Some(Command::Edit(command)) => {
+            let mut messages = Messages::default();
+            storage.update(&mut |config| {
+                messages = run_command(command, config)?;
+                Ok(())
+            })?;
+            messages.print();
+            return Ok(());
         }
-        return Ok(());
+        Some(Command::Doctor { format }) => return run_doctor(&storage, *format),
+        Some(Command::History { limit }) => {
+            for commit in versioned(&storage)?.history(*limit)? {
+                println!("{}  {}  {}", commit.id, commit.time, commit.summary);
+            }
+            return Ok(());
+        }
+        Some(Command::Export { format, output }) => {
+            return run_export(&storage.load()?, *format, output.as_deref());
+        }
+        Some(Command::Resolve { names, format }) => {
+            return run_resolve(&storage.load()?, names, *format);
+        }
+        Some(Command::Origin { names }) => return run_origin(&storage, names),
+        Some(Command::Sync) => {
+            println!("{}", versioned(&storage)?.sync()?);
+            return Ok(());
+        }
+        Some(Command::Backup(command)) => return run_backup(&storage, command),
+        Some(Command::Undo) => return run_undo(&storage, false),
+        Some(Command::Redo) => return run_undo(&storage, true),
+        None => {}
     }
 
     let config = storage.load()?;
 
     if args.links.is_empty() {
-        print_config(&config);
+        match args.format {
+            ListFormat::Table => print_config(&config),
+            ListFormat::Json => print!("{}", export::json(&config)?),
+            ListFormat::Toml => {
+                print!(
+                    "{}",
+                    toml::to_string(&config).context("Failed to serialize config")?
+                )
+            }
+            ListFormat::Tsv => print!("{}", export::tsv(&config)),
+        }
     } else {
-        open_links(&args.links, &config)?;
+        let mode = if args.dry_run {
+            OpenMode::DryRun
+        } else if args.print {
+            OpenMode::Print
+        } else {
+            OpenMode::Open
+        };
+        open_links(&args.links, &config, mode)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_CONFIG;
+
+    /// Run a subcommand the way `storage.update` would: a failed command
+    /// leaves the config untouched.
+    fn run_on(config: &mut Config, args: &[&str]) -> Result<Messages> {
+        let args = Args::parse_from(std::iter::once("dkdc-links").chain(args.iter().copied()));
+        let mut updated = config.clone();
+        let Some(Command::Edit(command)) = args.command else {
+            panic!("not an edit command");
+        };
+        let messages = run_command(&command, &mut updated)?;
+        *config = updated;
+        Ok(messages)
+    }
+
+    #[test]
+    fn test_group_remove_refuses_nested_group() {
+        let mut config: Config = toml::from_str(
+            r#"
+[links]
+github = "https://github.com"
+
+[groups]
+dev = ["github"]
+all = ["dev"]
+"#,
+        )
+        .unwrap();
+
+        let err = run_on(&mut config, &["group", "remove", "dev"]).unwrap_err();
+        assert!(err.to_string().contains("all"));
+        let err = run_on(&mut config, &["group", "remove", "dev", "github"]).unwrap_err();
+        assert!(err.to_string().contains("all"));
+
+        run_on(&mut config, &["group", "remove", "all"]).unwrap();
+        run_on(&mut config, &["group", "remove", "dev", "github"]).unwrap();
+        assert!(config.groups.is_empty());
+    }
+
+    #[test]
+    fn test_names_like_commands_open_after_double_dash() {
+        let args = Args::parse_from(["dkdc-links", "--print", "sync"]);
+        assert!(matches!(args.command, Some(Command::Sync)));
+        assert!(args.links.is_empty());
+
+        let args = Args::parse_from(["dkdc-links", "--print", "--", "sync", "import"]);
+        assert!(args.command.is_none());
+        assert_eq!(args.links, vec!["sync", "import"]);
+
+        let mut config = Config::default();
+        let messages = run_on(&mut config, &["add", "sync", "https://example.com"]).unwrap();
+        assert_eq!(
+            messages.warnings,
+            vec!["'sync' is also a command; open it with `dkdc-links -- sync`"]
+        );
+        let messages = run_on(&mut config, &["add", "synced", "https://example.com"]).unwrap();
+        assert!(messages.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_edits_return_messages() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let messages = run_on(&mut config, &["mv", "link1", "crates"]).unwrap();
+        assert_eq!(messages.lines, vec!["renamed link link1 -> crates"]);
+
+        let messages = run_on(&mut config, &["rm", "crates", "--orphan"]).unwrap();
+        assert_eq!(messages.lines, vec!["removed link crates"]);
+        assert_eq!(
+            messages.warnings,
+            vec![
+                "alias 'alias1' still refers to 'crates'",
+                "alias 'a1' still refers to 'crates'",
+                "group 'dev' still refers to 'crates'",
+            ]
+        );
+    }
+}
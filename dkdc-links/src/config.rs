@@ -1,19 +1,280 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use serde::de::{value::MapAccessDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use toml::value::{Date, Datetime, Offset, Time};
+
+use crate::diagnostic::{closest, Diagnostic, DiagnosticKind, Severity};
+use crate::strings;
 
 const DEFAULT_EDITOR: &str = "vi";
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+pub use indexmap::IndexMap;
+
+/// Links, aliases and groups, each kept in file (or insertion) order so that
+/// saving an unchanged config reproduces it exactly.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
-    pub aliases: HashMap<String, String>,
+    pub aliases: IndexMap<String, String>,
     #[serde(default)]
-    pub links: HashMap<String, String>,
+    pub links: IndexMap<String, Link>,
     #[serde(default)]
-    pub groups: HashMap<String, Vec<String>>,
+    pub groups: IndexMap<String, Vec<String>>,
+    /// The [`Config::fingerprint`] of the stored config this one was loaded
+    /// from, so saving it can tell whether the storage changed in between.
+    /// `None` for a config that wasn't loaded, which saves unconditionally.
+    #[serde(skip)]
+    pub snapshot: Option<u64>,
+}
+
+/// One of the config's three tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    Links,
+    Aliases,
+    Groups,
+}
+
+impl Section {
+    /// Every section, in the order they are compared and diffed.
+    pub const ALL: [Section; 3] = [Section::Links, Section::Aliases, Section::Groups];
+
+    /// Sections in the order a name is looked up: a group shadows an alias,
+    /// which shadows a link.
+    pub const PRECEDENCE: [Section; 3] = [Section::Groups, Section::Aliases, Section::Links];
+
+    /// The table name, e.g. `links`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Section::Links => "links",
+            Section::Aliases => "aliases",
+            Section::Groups => "groups",
+        }
+    }
+
+    /// What one entry is called, e.g. `link`.
+    pub fn kind(self) -> &'static str {
+        match self {
+            Section::Links => "link",
+            Section::Aliases => "alias",
+            Section::Groups => "group",
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a delete does with the aliases and groups that refer to the deleted
+/// name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeleteMode {
+    /// Delete nothing if anything refers to the name.
+    #[default]
+    Refuse,
+    /// Also delete the aliases that point at it and drop it (and those
+    /// aliases) from every group.
+    Cascade,
+    /// Delete only the name, leaving references to it dangling.
+    Orphan,
+}
+
+/// An alias or group that refers to a name, directly or through an alias.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dependent {
+    Alias(String),
+    Group(String),
+}
+
+impl Dependent {
+    pub fn name(&self) -> &str {
+        match self {
+            Dependent::Alias(name) | Dependent::Group(name) => name,
+        }
+    }
+}
+
+impl fmt::Display for Dependent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dependent::Alias(name) => write!(f, "alias '{name}'"),
+            Dependent::Group(name) => write!(f, "group '{name}'"),
+        }
+    }
+}
+
+/// A link's URL plus optional description, tags and timestamps.
+///
+/// Written as a bare URL string when it carries no metadata and as an inline
+/// table otherwise; both forms are accepted when reading.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(remote = "Self", deny_unknown_fields)]
+pub struct Link {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<Datetime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated: Option<Datetime>,
+}
+
+impl Link {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether anything beyond the URL is set.
+    pub fn has_metadata(&self) -> bool {
+        self.description.is_some()
+            || !self.tags.is_empty()
+            || self.created.is_some()
+            || self.updated.is_some()
+    }
+
+    /// Whether `tag` is one of this link's tags.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// The URL, description and tags joined for substring filtering.
+    pub fn filter_text(&self) -> String {
+        let mut text = self.url.clone();
+        for part in self.description.iter().chain(&self.tags) {
+            text.push(' ');
+            text.push_str(part);
+        }
+        text
+    }
+
+    /// Description and tags on one line, e.g. `code hosting #dev #work`.
+    pub fn summary(&self) -> String {
+        let tags = self.tags.iter().map(|t| format!("#{t}"));
+        self.description
+            .iter()
+            .cloned()
+            .chain(tags)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Serialize for Link {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if self.has_metadata() {
+            Link::serialize(self, serializer)
+        } else {
+            serializer.serialize_str(&self.url)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Link {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct LinkVisitor;
+
+        impl<'de> Visitor<'de> for LinkVisitor {
+            type Value = Link;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a URL string or a table with a `url` key")
+            }
+
+            fn visit_str<E>(self, url: &str) -> std::result::Result<Link, E> {
+                Ok(Link::new(url))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<Link, A::Error> {
+                Link::deserialize(MapAccessDeserializer::new(map))
+            }
+        }
+
+        deserializer.deserialize_any(LinkVisitor)
+    }
+}
+
+/// The current UTC time, to the second.
+pub(crate) fn now() -> Datetime {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    utc(secs)
+}
+
+/// The UTC time `secs` seconds after the Unix epoch.
+pub(crate) fn utc(secs: u64) -> Datetime {
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    Datetime {
+        date: Some(Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }),
+        time: Some(Time {
+            hour: (rem / 3_600) as u8,
+            minute: (rem % 3_600 / 60) as u8,
+            second: (rem % 60) as u8,
+            nanosecond: 0,
+        }),
+        offset: Some(Offset::Z),
+    }
+}
+
+/// Seconds since the Unix epoch for a datetime with a date, treating a
+/// missing time as midnight and a missing offset as UTC. The inverse of
+/// [`utc`].
+pub(crate) fn unix_seconds(datetime: &Datetime) -> Option<u64> {
+    let date = datetime.date?;
+    let (year, month, day) = (
+        i64::from(date.year),
+        i64::from(date.month),
+        i64::from(date.day),
+    );
+
+    // Civil date to days since 1970-01-01 (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let time = datetime.time.map_or(0, |t| {
+        i64::from(t.hour) * 3_600 + i64::from(t.minute) * 60 + i64::from(t.second)
+    });
+    let offset = match datetime.offset {
+        Some(Offset::Custom { minutes }) => i64::from(minutes) * 60,
+        _ => 0,
+    };
+    u64::try_from(days * 86_400 + time - offset).ok()
 }
 
 pub const DEFAULT_CONFIG: &str = r#"# dkdc-links config file
@@ -30,37 +291,464 @@ dev = ["alias1", "alias2"]
 "#;
 
 impl Config {
-    pub fn validate(&self) -> Vec<String> {
-        let mut warnings = Vec::new();
+    /// A hash of the links, aliases and groups, which changes whenever any of
+    /// them does.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        toml::to_string(self).unwrap_or_default().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// This config, marked as loaded from storage that holds exactly it.
+    pub(crate) fn loaded(mut self) -> Self {
+        self.snapshot = Some(self.fingerprint());
+        self
+    }
+
+    /// Whether saving this config over `stored` would drop changes made since
+    /// it was loaded: the storage holds neither what it was loaded from nor
+    /// this config already. A missing or unreadable store counts as changed.
+    pub(crate) fn is_stale(&self, stored: Option<&Config>) -> bool {
+        let Some(snapshot) = self.snapshot else {
+            return false;
+        };
+        stored.is_none_or(|stored| {
+            let current = stored.fingerprint();
+            current != snapshot && current != self.fingerprint()
+        })
+    }
+
+    /// Check that every alias and group entry points at something that
+    /// exists and that no group contains itself.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
 
         for (alias, target) in &self.aliases {
             if !self.links.contains_key(target) {
-                warnings.push(format!(
-                    "alias '{alias}' points to '{target}' which is not in [links]"
-                ));
+                let suggestion = match closest(target, self.links.keys().map(String::as_str)) {
+                    Some(name) => format!("did you mean '{name}'?"),
+                    None => format!(
+                        "add a link named '{target}' or point '{alias}' at an existing link"
+                    ),
+                };
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Error,
+                        DiagnosticKind::MissingAliasTarget,
+                        "aliases",
+                        alias,
+                        format!("alias '{alias}' points to '{target}' which is not in [links]"),
+                    )
+                    .with_suggestion(Some(suggestion)),
+                );
             }
         }
 
         for (group, entries) in &self.groups {
             for entry in entries {
-                if !self.aliases.contains_key(entry) && !self.links.contains_key(entry) {
-                    warnings.push(format!(
-                        "group '{group}' contains '{entry}' which is not in [aliases] or [links]"
-                    ));
+                if !self.contains(entry) {
+                    let suggestion = match closest(entry, self.names()) {
+                        Some(name) => format!("did you mean '{name}'?"),
+                        None => format!("remove '{entry}' from group '{group}'"),
+                    };
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Error,
+                            DiagnosticKind::MissingGroupEntry,
+                            "groups",
+                            group,
+                            format!(
+                                "group '{group}' contains '{entry}' which is not in [aliases], [links] or [groups]"
+                            ),
+                        )
+                        .with_entry(entry)
+                        .with_suggestion(Some(suggestion)),
+                    );
+                }
+            }
+        }
+
+        for cycle in self.group_cycles() {
+            let (group, entry) = (cycle[cycle.len() - 2], cycle[cycle.len() - 1]);
+            diagnostics.push(
+                Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::GroupCycle,
+                    "groups",
+                    group,
+                    format!("group cycle: {}", cycle.join(" -> ")),
+                )
+                .with_entry(entry)
+                .with_suggestion(Some(format!("remove '{entry}' from group '{group}'"))),
+            );
+        }
+
+        diagnostics
+    }
+
+    /// Every link, alias and group name.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.links
+            .keys()
+            .chain(self.aliases.keys())
+            .chain(self.groups.keys())
+            .map(String::as_str)
+    }
+
+    /// Whether a name exists in any of [links], [aliases] or [groups].
+    pub fn contains(&self, name: &str) -> bool {
+        self.links.contains_key(name)
+            || self.aliases.contains_key(name)
+            || self.groups.contains_key(name)
+    }
+
+    /// Whether `name` is defined in `section`.
+    pub fn has(&self, section: Section, name: &str) -> bool {
+        match section {
+            Section::Links => self.links.contains_key(name),
+            Section::Aliases => self.aliases.contains_key(name),
+            Section::Groups => self.groups.contains_key(name),
+        }
+    }
+
+    /// Remove `name` from `section` alone, leaving anything that refers to it
+    /// alone too. Returns whether it was there.
+    pub fn remove(&mut self, section: Section, name: &str) -> bool {
+        match section {
+            Section::Links => self.links.shift_remove(name).is_some(),
+            Section::Aliases => self.aliases.shift_remove(name).is_some(),
+            Section::Groups => self.groups.shift_remove(name).is_some(),
+        }
+    }
+
+    /// Every (section, name), section by section in [`Section::ALL`] order.
+    pub fn entries(&self) -> impl Iterator<Item = (Section, &str)> {
+        let links = self
+            .links
+            .keys()
+            .map(|name| (Section::Links, name.as_str()));
+        let aliases = self
+            .aliases
+            .keys()
+            .map(|name| (Section::Aliases, name.as_str()));
+        let groups = self
+            .groups
+            .keys()
+            .map(|name| (Section::Groups, name.as_str()));
+        links.chain(aliases).chain(groups)
+    }
+
+    /// The section `name` is defined in, if any, in lookup order.
+    pub fn section_of(&self, name: &str) -> Option<Section> {
+        Section::PRECEDENCE
+            .into_iter()
+            .find(|section| self.has(*section, name))
+    }
+
+    /// Links, aliases and groups share one namespace: error if `name` is
+    /// already defined anywhere other than `section`.
+    pub fn check_name_free(&self, name: &str, section: Section) -> Result<()> {
+        let taken = Section::ALL
+            .into_iter()
+            .find(|s| *s != section && self.has(*s, name));
+        match taken {
+            Some(taken) => anyhow::bail!(strings::err_name_taken(name, taken.as_str())),
+            None => Ok(()),
+        }
+    }
+
+    /// Error if `new` is empty or defined in any section; the target of a
+    /// rename must be a fresh name.
+    fn check_rename_target(&self, new: &str) -> Result<()> {
+        if new.is_empty() {
+            anyhow::bail!("new name must not be empty");
+        }
+        match self.section_of(new) {
+            Some(section) => anyhow::bail!(strings::err_name_taken(new, section.as_str())),
+            None => Ok(()),
+        }
+    }
+
+    /// Add a link or change an existing link's URL, keeping its metadata.
+    pub fn add_link(&mut self, name: &str, url: &str) -> Result<()> {
+        let mut link = self.links.get(name).cloned().unwrap_or_default();
+        link.url = url.to_string();
+        self.put_link(name, link)
+    }
+
+    /// Add or replace a link. Links written as tables, because they have a
+    /// description, tags or timestamps already, are stamped too: `created`
+    /// when new, `updated` when their URL, description or tags change. A
+    /// plain link stays a plain `name = "url"`.
+    pub fn put_link(&mut self, name: &str, mut link: Link) -> Result<()> {
+        if name.is_empty() || link.url.is_empty() {
+            anyhow::bail!("link name and url must not be empty");
+        }
+        self.check_name_free(name, Section::Links)?;
+        match self.links.get_mut(name) {
+            Some(existing) => {
+                let changed = existing.url != link.url
+                    || existing.description != link.description
+                    || existing.tags != link.tags;
+                let stamp = existing.has_metadata() || link.has_metadata();
+                link.created = link.created.or(existing.created);
+                link.updated = if changed && stamp {
+                    Some(now())
+                } else {
+                    link.updated.or(existing.updated)
+                };
+                *existing = link;
+            }
+            None => {
+                if link.has_metadata() {
+                    link.created.get_or_insert_with(now);
+                }
+                self.links.insert(name.to_string(), link);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add or update an alias, checking that its target exists in [links].
+    pub fn add_alias(&mut self, alias: &str, target: &str) -> Result<()> {
+        if alias.is_empty() || target.is_empty() {
+            anyhow::bail!("alias name and target must not be empty");
+        }
+        self.check_name_free(alias, Section::Aliases)?;
+        if !self.links.contains_key(target) {
+            anyhow::bail!(strings::err_alias_target_missing(target));
+        }
+        self.aliases.insert(alias.to_string(), target.to_string());
+        Ok(())
+    }
+
+    /// Add or replace a group, checking that every entry exists in
+    /// [aliases], [links] or [groups] and that no group ends up containing
+    /// itself.
+    pub fn add_group(&mut self, name: &str, entries: Vec<String>) -> Result<()> {
+        if name.is_empty() || entries.is_empty() {
+            anyhow::bail!("group name and entries must not be empty");
+        }
+        self.check_name_free(name, Section::Groups)?;
+        let missing = self.missing_entries(&entries);
+        if !missing.is_empty() {
+            anyhow::bail!(strings::err_group_entries_missing(&missing));
+        }
+        let previous = self.groups.insert(name.to_string(), entries);
+        let cycle = self
+            .group_cycles()
+            .into_iter()
+            .find(|cycle| cycle.contains(&name))
+            .map(|cycle| cycle.join(" -> "));
+        if let Some(cycle) = cycle {
+            match previous {
+                Some(entries) => self.groups.insert(name.to_string(), entries),
+                None => self.groups.shift_remove(name),
+            };
+            anyhow::bail!(strings::err_group_cycle(&cycle));
+        }
+        Ok(())
+    }
+
+    /// Remove a link.
+    pub fn remove_link(&mut self, name: &str) -> Result<Link> {
+        self.links
+            .shift_remove(name)
+            .with_context(|| format!("link '{name}' not found"))
+    }
+
+    /// Remove an alias.
+    pub fn remove_alias(&mut self, name: &str) -> Result<String> {
+        self.aliases
+            .shift_remove(name)
+            .with_context(|| format!("alias '{name}' not found"))
+    }
+
+    /// Remove a group.
+    pub fn remove_group(&mut self, name: &str) -> Result<Vec<String>> {
+        self.groups
+            .shift_remove(name)
+            .with_context(|| format!("group '{name}' not found"))
+    }
+
+    /// The aliases that point at `name` and the groups that list `name` or
+    /// one of those aliases.
+    pub fn dependents(&self, name: &str) -> Vec<Dependent> {
+        let aliases: Vec<&str> = self
+            .aliases
+            .iter()
+            .filter(|(alias, target)| *target == name && *alias != name)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        let groups = self
+            .groups
+            .iter()
+            .filter(|(group, entries)| {
+                *group != name
+                    && entries
+                        .iter()
+                        .any(|e| e == name || aliases.contains(&e.as_str()))
+            })
+            .map(|(group, _)| Dependent::Group(group.clone()));
+        aliases
+            .iter()
+            .map(|alias| Dependent::Alias(alias.to_string()))
+            .chain(groups)
+            .collect()
+    }
+
+    /// The dependents of any of `names` that are not themselves in `names`,
+    /// i.e. what deleting all of them would affect.
+    pub fn external_dependents<S: AsRef<str>>(&self, names: &[S]) -> Vec<Dependent> {
+        let mut dependents = Vec::new();
+        for name in names {
+            for dependent in self.dependents(name.as_ref()) {
+                let inside = names.iter().any(|n| n.as_ref() == dependent.name());
+                if !inside && !dependents.contains(&dependent) {
+                    dependents.push(dependent);
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Delete `name` from every section it is in, handling whatever refers
+    /// to it according to `mode`. Returns the affected aliases and groups.
+    pub fn delete(&mut self, name: &str, mode: DeleteMode) -> Result<Vec<Dependent>> {
+        if !self.contains(name) {
+            anyhow::bail!("'{name}' not found in [links], [aliases] or [groups]");
+        }
+        let dependents = self.dependents(name);
+        match mode {
+            DeleteMode::Refuse if !dependents.is_empty() => {
+                anyhow::bail!(strings::err_has_dependents(name, &dependents));
+            }
+            DeleteMode::Cascade => {
+                let mut removed = vec![name.to_string()];
+                for dependent in &dependents {
+                    if let Dependent::Alias(alias) = dependent {
+                        self.aliases.shift_remove(alias);
+                        removed.push(alias.clone());
+                    }
+                }
+                for entries in self.groups.values_mut() {
+                    entries.retain(|e| !removed.contains(e));
+                }
+            }
+            _ => {}
+        }
+        self.links.shift_remove(name);
+        self.aliases.shift_remove(name);
+        self.groups.shift_remove(name);
+        Ok(dependents)
+    }
+
+    /// Names of links carrying `tag`, in file order.
+    pub fn links_tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a str> {
+        self.links
+            .iter()
+            .filter(move |(_, link)| link.has_tag(tag))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Entries that are not a link, alias or group.
+    pub fn missing_entries<'a>(&self, entries: &'a [String]) -> Vec<&'a str> {
+        entries
+            .iter()
+            .filter(|e| !self.contains(e))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The links and aliases a group reaches, expanding nested groups
+    /// depth-first. Each name appears once, and cycles are cut where they
+    /// close.
+    pub fn group_members<'a>(&'a self, group: &str) -> Vec<&'a str> {
+        self.member_paths(group)
+            .into_iter()
+            .filter_map(|path| path.last().copied())
+            .collect()
+    }
+
+    /// The members of [`Config::group_members`], each with the groups
+    /// leading to it, e.g. `["all", "dev", "gh"]`.
+    pub fn member_paths<'a>(&'a self, group: &str) -> Vec<Vec<&'a str>> {
+        let mut members = Vec::new();
+        if let Some((group, _)) = self.groups.get_key_value(group) {
+            self.collect_members(group, &mut Vec::new(), &mut members);
+        }
+        members
+    }
+
+    fn collect_members<'a>(
+        &'a self,
+        group: &'a str,
+        path: &mut Vec<&'a str>,
+        members: &mut Vec<Vec<&'a str>>,
+    ) {
+        path.push(group);
+        for entry in &self.groups[group] {
+            let entry = entry.as_str();
+            if self.groups.contains_key(entry) {
+                if !path.contains(&entry) {
+                    self.collect_members(entry, path, members);
                 }
+            } else if !members.iter().any(|m| m.last() == Some(&entry)) {
+                let mut member = path.clone();
+                member.push(entry);
+                members.push(member);
             }
         }
+        path.pop();
+    }
 
-        warnings
+    /// Every cycle among groups, as the path that closes it, e.g.
+    /// `["a", "b", "a"]`.
+    pub fn group_cycles(&self) -> Vec<Vec<&str>> {
+        let mut cycles = Vec::new();
+        let mut done = Vec::new();
+        for group in self.groups.keys() {
+            self.find_cycles(group, &mut Vec::new(), &mut done, &mut cycles);
+        }
+        cycles
     }
 
-    /// Rename a link key and cascade to all aliases that target it.
+    fn find_cycles<'a>(
+        &'a self,
+        group: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut Vec<&'a str>,
+        cycles: &mut Vec<Vec<&'a str>>,
+    ) {
+        if done.contains(&group) {
+            return;
+        }
+        if let Some(start) = path.iter().position(|g| *g == group) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(group);
+            cycles.push(cycle);
+            return;
+        }
+        path.push(group);
+        for entry in &self.groups[group] {
+            if self.groups.contains_key(entry.as_str()) {
+                self.find_cycles(entry, path, done, cycles);
+            }
+        }
+        path.pop();
+        done.push(group);
+    }
+
+    /// Rename a link key and cascade to the aliases that target it and the
+    /// groups that list it. The new name must not be in use.
     pub fn rename_link(&mut self, old: &str, new: &str) -> Result<()> {
-        let url = self
+        let index = self
             .links
-            .remove(old)
+            .get_index_of(old)
             .with_context(|| format!("link '{old}' not found"))?;
-        self.links.insert(new.to_string(), url);
+        self.check_rename_target(new)?;
+        let (_, link) = self.links.shift_remove_index(index).expect("index exists");
+        self.links.shift_insert(index, new.to_string(), link);
 
         // Update aliases that point to the old name
         for target in self.aliases.values_mut() {
@@ -68,19 +756,49 @@ impl Config {
                 *target = new.to_string();
             }
         }
+        self.rename_group_entries(old, new);
 
         Ok(())
     }
 
-    /// Rename an alias key and cascade to all groups that reference it.
+    /// Rename an alias key and cascade to all groups that reference it. The
+    /// new name must not be in use.
     pub fn rename_alias(&mut self, old: &str, new: &str) -> Result<()> {
-        let target = self
+        let index = self
             .aliases
-            .remove(old)
+            .get_index_of(old)
             .with_context(|| format!("alias '{old}' not found"))?;
-        self.aliases.insert(new.to_string(), target);
+        self.check_rename_target(new)?;
+        let (_, target) = self
+            .aliases
+            .shift_remove_index(index)
+            .expect("index exists");
+        self.aliases.shift_insert(index, new.to_string(), target);
+
+        self.rename_group_entries(old, new);
+
+        Ok(())
+    }
+
+    /// Rename a group and cascade to the groups that include it. The new
+    /// name must not be in use.
+    pub fn rename_group(&mut self, old: &str, new: &str) -> Result<()> {
+        let index = self
+            .groups
+            .get_index_of(old)
+            .with_context(|| format!("group '{old}' not found"))?;
+        self.check_rename_target(new)?;
+        let (_, entries) = self.groups.shift_remove_index(index).expect("index exists");
+        self.groups.shift_insert(index, new.to_string(), entries);
 
-        // Update group entries that reference the old name
+        // Nested references, including a group that lists itself
+        self.rename_group_entries(old, new);
+
+        Ok(())
+    }
+
+    /// Point every group entry naming `old` at `new`.
+    fn rename_group_entries(&mut self, old: &str, new: &str) {
         for entries in self.groups.values_mut() {
             for entry in entries.iter_mut() {
                 if entry == old {
@@ -88,11 +806,61 @@ impl Config {
                 }
             }
         }
+    }
+
+    /// Append entries to a group, creating it if needed. Entries already in
+    /// the group are skipped; the same checks as [`Config::add_group`] apply.
+    pub fn add_to_group(&mut self, name: &str, entries: &[String]) -> Result<()> {
+        let mut merged = self.groups.get(name).cloned().unwrap_or_default();
+        for entry in entries {
+            if !merged.contains(entry) {
+                merged.push(entry.clone());
+            }
+        }
+        self.add_group(name, merged)
+    }
 
+    /// Remove entries from a group, leaving the group in place even if it
+    /// ends up empty.
+    pub fn remove_from_group(&mut self, name: &str, entries: &[String]) -> Result<()> {
+        let group = self
+            .groups
+            .get_mut(name)
+            .with_context(|| format!("group '{name}' not found"))?;
+        if let Some(entry) = entries.iter().find(|e| !group.contains(e)) {
+            anyhow::bail!("'{entry}' is not in group '{name}'");
+        }
+        group.retain(|e| !entries.contains(e));
+        Ok(())
+    }
+
+    /// Put a group's entries in a new order. `order` must contain exactly
+    /// the group's current entries.
+    pub fn reorder_group(&mut self, name: &str, order: &[String]) -> Result<()> {
+        let group = self
+            .groups
+            .get_mut(name)
+            .with_context(|| format!("group '{name}' not found"))?;
+        let mut current = group.clone();
+        let mut wanted = order.to_vec();
+        current.sort();
+        wanted.sort();
+        if current != wanted {
+            anyhow::bail!("new order for group '{name}' must list exactly its current entries");
+        }
+        *group = order.to_vec();
         Ok(())
     }
 }
 
+/// Split a comma-separated list of group entries, dropping blanks.
+pub fn parse_entries(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
 pub fn edit_config(config_path: &Path) -> Result<()> {
     let editor = std::env::var("EDITOR").unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
 
@@ -112,7 +880,7 @@ pub fn edit_config(config_path: &Path) -> Result<()> {
 
 fn print_section<V>(
     name: &str,
-    map: &HashMap<String, V>,
+    map: &IndexMap<String, V>,
     format_value: impl Fn(&V) -> Cow<'_, str>,
 ) {
     if map.is_empty() {
@@ -122,12 +890,9 @@ fn print_section<V>(
     println!("{name}:");
     println!();
 
-    let mut entries: Vec<_> = map.iter().collect();
-    entries.sort_unstable_by_key(|(k, _)| k.as_str());
-
-    let max_key_len = entries.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    let max_key_len = map.keys().map(String::len).max().unwrap_or(0);
 
-    for (key, value) in entries {
+    for (key, value) in map {
         println!("• {key:<max_key_len$} | {}", format_value(value));
     }
 
@@ -136,7 +901,13 @@ fn print_section<V>(
 
 pub fn print_config(config: &Config) {
     print_section("aliases", &config.aliases, |v| Cow::Borrowed(v));
-    print_section("links", &config.links, |v| Cow::Borrowed(v));
+    print_section("links", &config.links, |link| {
+        if link.description.is_none() && link.tags.is_empty() {
+            Cow::Borrowed(&link.url)
+        } else {
+            Cow::Owned(format!("{}  ({})", link.url, link.summary()))
+        }
+    });
     print_section("groups", &config.groups, |v| {
         Cow::Owned(format!("[{}]", v.join(", ")))
     });
@@ -161,12 +932,21 @@ dev = ["gh"]
         let config: Config = toml::from_str(toml).unwrap();
         assert_eq!(config.aliases.get("gh"), Some(&"github".to_string()));
         assert_eq!(
-            config.links.get("github"),
-            Some(&"https://github.com".to_string())
+            config.links.get("github").map(|l| l.url.as_str()),
+            Some("https://github.com")
         );
         assert_eq!(config.groups.get("dev"), Some(&vec!["gh".to_string()]));
     }
 
+    #[test]
+    fn test_unix_seconds_inverts_utc() {
+        for secs in [0, 951_782_400, 1_792_229_412] {
+            assert_eq!(unix_seconds(&utc(secs)), Some(secs));
+        }
+        let datetime: Datetime = "2026-10-17T11:30:12+02:00".parse().unwrap();
+        assert_eq!(unix_seconds(&datetime), Some(1_792_229_412));
+    }
+
     #[test]
     fn test_parse_empty_config() {
         let toml = "";
@@ -185,8 +965,8 @@ rust = "https://rust-lang.org"
         let config: Config = toml::from_str(toml).unwrap();
         assert!(config.aliases.is_empty());
         assert_eq!(
-            config.links.get("rust"),
-            Some(&"https://rust-lang.org".to_string())
+            config.links.get("rust").map(|l| l.url.as_str()),
+            Some("https://rust-lang.org")
         );
         assert!(config.groups.is_empty());
     }
@@ -197,7 +977,7 @@ rust = "https://rust-lang.org"
         config.aliases.insert("a".to_string(), "b".to_string());
         config
             .links
-            .insert("b".to_string(), "https://example.com".to_string());
+            .insert("b".to_string(), Link::new("https://example.com"));
         config.groups.insert("g".to_string(), vec!["a".to_string()]);
 
         let serialized = toml::to_string(&config).unwrap();
@@ -232,9 +1012,11 @@ broken = "nonexistent"
 real = "https://example.com"
 "#;
         let config: Config = toml::from_str(toml).unwrap();
-        let warnings = config.validate();
-        assert_eq!(warnings.len(), 1);
-        assert!(warnings[0].contains("nonexistent"));
+        let diagnostics = config.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::MissingAliasTarget);
+        assert!(diagnostics[0].is_for("aliases", "broken"));
+        assert!(diagnostics[0].message.contains("nonexistent"));
     }
 
     #[test]
@@ -255,6 +1037,26 @@ github = "https://github.com"
         assert_eq!(config.aliases.get("g"), Some(&"gh-link".to_string()));
     }
 
+    #[test]
+    fn test_rename_link_cascades_groups() {
+        let toml = r#"
+[aliases]
+gh = "github"
+
+[links]
+github = "https://github.com"
+
+[groups]
+dev = ["gh"]
+all = ["dev", "github"]
+"#;
+        let mut config: Config = toml::from_str(toml).unwrap();
+        config.rename_link("github", "hub").unwrap();
+        assert_eq!(config.groups["all"], vec!["dev", "hub"]);
+        assert_eq!(config.groups["dev"], vec!["gh"]);
+        assert!(config.missing_entries(&config.groups["all"]).is_empty());
+    }
+
     #[test]
     fn test_rename_alias_cascades_groups() {
         let toml = r#"
@@ -281,6 +1083,50 @@ all = ["gh", "other"]
         assert!(all.contains(&"other".to_string()));
     }
 
+    #[test]
+    fn test_parse_preserves_file_order() {
+        let toml = r#"
+[links]
+zeta = "https://z.example.com"
+alpha = "https://a.example.com"
+mid = "https://m.example.com"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let names: Vec<_> = config.links.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["zeta", "alpha", "mid"]);
+    }
+
+    #[test]
+    fn test_rename_keeps_position() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        config.rename_link("link1", "renamed").unwrap();
+        config.rename_alias("alias2", "second").unwrap();
+
+        let links: Vec<_> = config.links.keys().map(String::as_str).collect();
+        assert_eq!(links, vec!["renamed", "link2"]);
+        let aliases: Vec<_> = config.aliases.keys().map(String::as_str).collect();
+        assert_eq!(aliases, vec!["alias1", "a1", "second", "a2"]);
+    }
+
+    #[test]
+    fn test_section_of_follows_precedence() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        config.groups.insert("link1".into(), vec!["alias1".into()]);
+        assert_eq!(config.section_of("link1"), Some(Section::Groups));
+        assert!(config.remove(Section::Groups, "link1"));
+        assert_eq!(config.section_of("link1"), Some(Section::Links));
+        assert!(!config.remove(Section::Aliases, "link1"));
+        assert_eq!(config.section_of("nope"), None);
+    }
+
+    #[test]
+    fn test_serialization_is_deterministic() {
+        let config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let first = toml::to_string(&config).unwrap();
+        let reparsed: Config = toml::from_str(&first).unwrap();
+        assert_eq!(first, toml::to_string(&reparsed).unwrap());
+    }
+
     #[test]
     fn test_rename_nonexistent_link_errors() {
         let mut config = Config::default();
@@ -293,6 +1139,169 @@ all = ["gh", "other"]
         assert!(config.rename_alias("nope", "new").is_err());
     }
 
+    #[test]
+    fn test_names_are_unique_across_sections() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let err = config
+            .add_link("alias1", "https://example.com")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'alias1' already exists in [aliases]"));
+        assert!(config.add_alias("dev", "link1").is_err());
+        assert!(config
+            .add_group("link1", vec!["link2".to_string()])
+            .is_err());
+
+        // Updating an entry in its own section is fine
+        config.add_link("link1", "https://example.com").unwrap();
+        config.add_alias("alias1", "link2").unwrap();
+        config.add_group("dev", vec!["a1".to_string()]).unwrap();
+    }
+
+    #[test]
+    fn test_rename_refuses_existing_names() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let before = toml::to_string(&config).unwrap();
+        let err = config.rename_link("link1", "link2").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'link2' already exists in [links]"));
+        assert!(config.rename_link("link1", "dev").is_err());
+        assert!(config.rename_alias("alias1", "a2").is_err());
+        assert!(config.rename_alias("alias1", "link1").is_err());
+        assert!(config.rename_alias("alias1", "").is_err());
+        assert_eq!(toml::to_string(&config).unwrap(), before);
+    }
+
+    const DEPENDENTS: &str = r#"
+[links]
+github = "https://github.com"
+rust = "https://rust-lang.org"
+
+[aliases]
+gh = "github"
+
+[groups]
+dev = ["gh", "rust"]
+all = ["dev", "github"]
+"#;
+
+    #[test]
+    fn test_dependents_include_groups_reached_through_aliases() {
+        let config: Config = toml::from_str(DEPENDENTS).unwrap();
+        assert_eq!(
+            config.dependents("github"),
+            vec![
+                Dependent::Alias("gh".to_string()),
+                Dependent::Group("dev".to_string()),
+                Dependent::Group("all".to_string()),
+            ]
+        );
+        assert_eq!(
+            config.dependents("dev"),
+            vec![Dependent::Group("all".to_string())]
+        );
+        assert!(config.dependents("all").is_empty());
+        assert_eq!(
+            config.external_dependents(&["github", "gh", "dev"]),
+            vec![Dependent::Group("all".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_delete_refuses_when_referenced() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        let err = config.delete("github", DeleteMode::Refuse).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'github' is still used by alias 'gh', group 'dev', group 'all'"
+        );
+        assert!(config.links.contains_key("github"));
+
+        config.delete("all", DeleteMode::Refuse).unwrap();
+        assert!(!config.groups.contains_key("all"));
+        assert!(config.delete("all", DeleteMode::Refuse).is_err());
+    }
+
+    #[test]
+    fn test_delete_cascade_removes_references() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        let affected = config.delete("github", DeleteMode::Cascade).unwrap();
+        assert_eq!(affected.len(), 3);
+        assert!(!config.links.contains_key("github"));
+        assert!(config.aliases.is_empty());
+        assert_eq!(config.groups["dev"], vec!["rust"]);
+        assert_eq!(config.groups["all"], vec!["dev"]);
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_delete_orphan_leaves_references() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        config.delete("github", DeleteMode::Orphan).unwrap();
+        assert!(!config.links.contains_key("github"));
+        assert_eq!(config.aliases["gh"], "github");
+        assert_eq!(config.validate().len(), 2);
+    }
+
+    #[test]
+    fn test_rename_group_cascades_nested_groups() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        config.rename_group("dev", "work").unwrap();
+        let groups: Vec<_> = config.groups.keys().map(String::as_str).collect();
+        assert_eq!(groups, vec!["work", "all"]);
+        assert_eq!(config.groups["all"], vec!["work", "github"]);
+        assert_eq!(config.group_members("all"), vec!["gh", "rust", "github"]);
+
+        assert!(config.rename_group("work", "github").is_err());
+        assert!(config.rename_group("nope", "x").is_err());
+    }
+
+    #[test]
+    fn test_add_to_group() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        config
+            .add_to_group("dev", &["rust".to_string(), "github".to_string()])
+            .unwrap();
+        assert_eq!(config.groups["dev"], vec!["gh", "rust", "github"]);
+
+        config.add_to_group("new", &["gh".to_string()]).unwrap();
+        assert_eq!(config.groups["new"], vec!["gh"]);
+
+        assert!(config.add_to_group("dev", &["ghost".to_string()]).is_err());
+        assert!(config.add_to_group("dev", &["all".to_string()]).is_err());
+        assert_eq!(config.groups["dev"], vec!["gh", "rust", "github"]);
+    }
+
+    #[test]
+    fn test_remove_from_group() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        config
+            .remove_from_group("dev", &["gh".to_string()])
+            .unwrap();
+        assert_eq!(config.groups["dev"], vec!["rust"]);
+        assert!(config
+            .remove_from_group("dev", &["gh".to_string()])
+            .is_err());
+        assert!(config.remove_from_group("nope", &[]).is_err());
+    }
+
+    #[test]
+    fn test_reorder_group() {
+        let mut config: Config = toml::from_str(DEPENDENTS).unwrap();
+        config
+            .reorder_group("all", &["github".to_string(), "dev".to_string()])
+            .unwrap();
+        assert_eq!(config.groups["all"], vec!["github", "dev"]);
+        assert!(config
+            .reorder_group("all", &["github".to_string()])
+            .is_err());
+        assert!(config
+            .reorder_group("all", &["github".to_string(), "rust".to_string()])
+            .is_err());
+    }
+
     #[test]
     fn test_broken_group_entry_warns() {
         let toml = r#"
@@ -303,8 +1312,225 @@ real = "https://example.com"
 dev = ["real", "ghost"]
 "#;
         let config: Config = toml::from_str(toml).unwrap();
-        let warnings = config.validate();
-        assert_eq!(warnings.len(), 1);
-        assert!(warnings[0].contains("ghost"));
+        let diagnostics = config.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_for("groups", "dev"));
+        assert_eq!(diagnostics[0].entry.as_deref(), Some("ghost"));
+    }
+
+    #[test]
+    fn test_add_alias_requires_existing_link() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert!(config.add_alias("x", "nonexistent").is_err());
+        config.add_alias("x", "link1").unwrap();
+        assert_eq!(config.aliases.get("x"), Some(&"link1".to_string()));
+    }
+
+    #[test]
+    fn test_add_group_reports_missing_entries() {
+        let mut config: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let err = config
+            .add_group("g", vec!["link1".to_string(), "ghost".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("ghost"));
+        assert!(!config.groups.contains_key("g"));
+
+        config
+            .add_group("g", vec!["link1".to_string(), "a2".to_string()])
+            .unwrap();
+        assert_eq!(config.groups.get("g").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        assert_eq!(parse_entries(" a, b ,,c "), vec!["a", "b", "c"]);
+        assert!(parse_entries(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_link_with_metadata() {
+        let toml = r#"
+[links]
+plain = "https://example.com"
+github = { url = "https://github.com", description = "code hosting", tags = ["dev", "work"], created = 2024-01-02T03:04:05Z }
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert!(!config.links["plain"].has_metadata());
+        let github = &config.links["github"];
+        assert_eq!(github.url, "https://github.com");
+        assert_eq!(github.description.as_deref(), Some("code hosting"));
+        assert!(github.has_tag("work"));
+        assert_eq!(
+            github.created.as_ref().map(ToString::to_string).as_deref(),
+            Some("2024-01-02T03:04:05Z")
+        );
+        assert!(github.updated.is_none());
+    }
+
+    #[test]
+    fn test_link_table_requires_url() {
+        let toml = r#"
+[links]
+bad = { description = "no url" }
+"#;
+        let err = toml::from_str::<Config>(toml).unwrap_err();
+        assert!(err.to_string().contains("url"));
+    }
+
+    #[test]
+    fn test_plain_link_serializes_as_string() {
+        let mut config = Config::default();
+        config
+            .links
+            .insert("a".to_string(), Link::new("https://a.example.com"));
+        let mut tagged = Link::new("https://b.example.com");
+        tagged.tags = vec!["dev".to_string()];
+        config.links.insert("b".to_string(), tagged.clone());
+
+        let serialized = toml::to_string(&config).unwrap();
+        assert!(serialized.contains("a = \"https://a.example.com\""));
+        let reparsed: Config = toml::from_str(&serialized).unwrap();
+        assert_eq!(reparsed.links["b"], tagged);
+    }
+
+    #[test]
+    fn test_add_link_stamps_only_table_links() {
+        let mut config = Config::default();
+        config.add_link("rust", "https://rust-lang.org").unwrap();
+        config
+            .add_link("rust", "https://www.rust-lang.org")
+            .unwrap();
+        assert_eq!(config.links["rust"], Link::new("https://www.rust-lang.org"));
+
+        let mut described = Link::new("https://rust-lang.org");
+        described.description = Some("the language".to_string());
+        config.put_link("rust", described).unwrap();
+        let created = config.links["rust"].created;
+        assert!(created.is_none());
+        assert!(config.links["rust"].updated.is_some());
+
+        config
+            .put_link(
+                "crates",
+                Link {
+                    tags: vec!["rust".to_string()],
+                    ..Link::new("https://crates.io")
+                },
+            )
+            .unwrap();
+        assert!(config.links["crates"].created.is_some());
+        assert!(config.links["crates"].updated.is_none());
+
+        // Re-adding the same URL is not a change
+        config.add_link("crates", "https://crates.io").unwrap();
+        assert!(config.links["crates"].updated.is_none());
+        config.add_link("crates", "https://crates.io/").unwrap();
+        let link = &config.links["crates"];
+        assert!(link.updated.is_some());
+        assert_eq!(link.tags, vec!["rust"]);
+    }
+
+    #[test]
+    fn test_now_is_a_valid_datetime() {
+        let stamp = now().to_string();
+        let parsed: Datetime = stamp.parse().unwrap();
+        assert_eq!(parsed.to_string(), stamp);
+        assert!(stamp.ends_with('Z'));
+    }
+
+    #[test]
+    fn test_link_filter_text_and_summary() {
+        let link = Link {
+            url: "https://github.com".to_string(),
+            description: Some("code hosting".to_string()),
+            tags: vec!["dev".to_string(), "work".to_string()],
+            ..Link::default()
+        };
+        assert_eq!(
+            link.filter_text(),
+            "https://github.com code hosting dev work"
+        );
+        assert_eq!(link.summary(), "code hosting #dev #work");
+    }
+
+    const NESTED: &str = r#"
+[links]
+mail = "https://mail.example.com"
+cal = "https://cal.example.com"
+meet = "https://meet.example.com"
+
+[groups]
+email = ["mail", "cal"]
+standup = ["meet", "cal"]
+morning = ["email", "standup", "mail"]
+"#;
+
+    #[test]
+    fn test_group_members_expand_nested_groups() {
+        let config: Config = toml::from_str(NESTED).unwrap();
+        assert_eq!(config.group_members("morning"), vec!["mail", "cal", "meet"]);
+        assert_eq!(config.group_members("email"), vec!["mail", "cal"]);
+        assert!(config.group_members("nope").is_empty());
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_group_cycles_are_reported_with_path() {
+        let toml = r#"
+[links]
+x = "https://x.example.com"
+
+[groups]
+a = ["x", "b"]
+b = ["c"]
+c = ["a"]
+self = ["self"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let diagnostics = config.validate();
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["group cycle: a -> b -> c -> a", "group cycle: self -> self"]
+        );
+        assert!(diagnostics[0].is_for("groups", "c"));
+        assert_eq!(diagnostics[0].entry.as_deref(), Some("a"));
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        // Expansion still terminates
+        assert_eq!(config.group_members("b"), vec!["x"]);
+    }
+
+    #[test]
+    fn test_add_group_rejects_cycles() {
+        let mut config: Config = toml::from_str(NESTED).unwrap();
+        let err = config
+            .add_group("email", vec!["mail".to_string(), "morning".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("email -> morning -> email"));
+        assert_eq!(config.groups["email"], vec!["mail", "cal"]);
+
+        let err = config
+            .add_group("loop", vec!["loop".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("not found"));
+        assert!(!config.groups.contains_key("loop"));
+    }
+
+    #[test]
+    fn test_validate_suggests_close_names() {
+        let toml = r#"
+[aliases]
+gh = "githb"
+
+[links]
+github = "https://github.com"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let diagnostics = config.validate();
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(
+            diagnostics[0].suggestion.as_deref(),
+            Some("did you mean 'github'?")
+        );
     }
 }
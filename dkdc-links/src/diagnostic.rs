@@ -0,0 +1,300 @@
+//! Structured config problems: what is wrong, where, and how to fix it.
+
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+use toml_edit::{Document, Item};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    /// An alias whose target is not a link.
+    MissingAliasTarget,
+    /// A group entry that is not a link, alias or group.
+    MissingGroupEntry,
+    /// A group that ends up containing itself.
+    GroupCycle,
+    /// A name defined in more than one section.
+    NameCollision,
+    /// A link with the same URL as another link.
+    DuplicateUrl,
+    /// A link whose URL is malformed or not http(s).
+    InvalidUrl,
+    /// A link that no alias or group refers to.
+    UnusedLink,
+}
+
+/// A byte range in the config source, with the 1-based line and column of
+/// its start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    fn new(source: &str, range: std::ops::Range<usize>) -> Self {
+        let before = &source[..range.start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            start: range.start,
+            end: range.end,
+            line: before.matches('\n').count() + 1,
+            column: source[line_start..range.start].chars().count() + 1,
+        }
+    }
+}
+
+/// A problem with one entry of the config.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// `links`, `aliases` or `groups`.
+    pub section: String,
+    pub key: String,
+    /// The offending element of a group's entries, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        kind: DiagnosticKind,
+        section: &str,
+        key: &str,
+        message: String,
+    ) -> Self {
+        Self {
+            severity,
+            kind,
+            section: section.to_string(),
+            key: key.to_string(),
+            entry: None,
+            message,
+            suggestion: None,
+            span: None,
+        }
+    }
+
+    pub fn with_entry(mut self, entry: &str) -> Self {
+        self.entry = Some(entry.to_string());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+
+    /// Whether this diagnostic is about `key` in `section`.
+    pub fn is_for(&self, section: &str, key: &str) -> bool {
+        self.section == section && self.key == key
+    }
+
+    /// Render rustc-style, quoting the offending line when the span and
+    /// source are known.
+    pub fn render(&self, path: Option<&Path>, source: Option<&str>) -> String {
+        let mut out = format!("{}: {}\n", self.severity, self.message);
+
+        match (self.span, source) {
+            (Some(span), Some(source)) => {
+                let location =
+                    path.map_or_else(|| "config".to_string(), |p| p.display().to_string());
+                let line_text = source.lines().nth(span.line - 1).unwrap_or_default();
+                let line_no = span.line.to_string();
+                let pad = " ".repeat(line_no.len());
+                let available = line_text.chars().count() + 1 - span.column;
+                let width = source[span.start..span.end]
+                    .chars()
+                    .count()
+                    .clamp(1, available.max(1));
+
+                out.push_str(&format!(
+                    "{pad}--> {location}:{}:{}\n",
+                    span.line, span.column
+                ));
+                out.push_str(&format!("{pad} |\n"));
+                out.push_str(&format!("{line_no} | {line_text}\n"));
+                out.push_str(&format!(
+                    "{pad} | {}{}\n",
+                    " ".repeat(span.column - 1),
+                    "^".repeat(width)
+                ));
+            }
+            _ => {
+                if let Some(path) = path {
+                    out.push_str(&format!(" --> {}\n", path.display()));
+                }
+            }
+        }
+
+        if let Some(suggestion) = &self.suggestion {
+            out.push_str(&format!("  = help: {suggestion}\n"));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Fill in source spans from the TOML the config was parsed from.
+pub fn locate(diagnostics: &mut [Diagnostic], source: &str) {
+    let Ok(doc) = Document::parse(source) else {
+        return;
+    };
+    for diagnostic in diagnostics {
+        let Some((key, item)) = doc
+            .get(&diagnostic.section)
+            .and_then(Item::as_table_like)
+            .and_then(|table| table.get_key_value(&diagnostic.key))
+        else {
+            continue;
+        };
+        let range = match (&diagnostic.entry, item.as_array()) {
+            (Some(entry), Some(array)) => array
+                .iter()
+                .find(|v| v.as_str() == Some(entry))
+                .and_then(|v| v.span()),
+            _ => item.span().or_else(|| key.span()),
+        };
+        diagnostic.span = range.map(|range| Span::new(source, range));
+    }
+}
+
+/// The candidate closest to `name` by edit distance, if it is close enough
+/// to be a likely typo.
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"[aliases]
+gh = "githb"
+
+[links]
+github = "https://github.com"
+
+[groups]
+dev = ["gh", "ghost"]
+"#;
+
+    fn diagnostics() -> Vec<Diagnostic> {
+        vec![
+            Diagnostic::new(
+                Severity::Error,
+                DiagnosticKind::MissingAliasTarget,
+                "aliases",
+                "gh",
+                "alias 'gh' points to 'githb' which is not in [links]".to_string(),
+            )
+            .with_suggestion(Some("did you mean 'github'?".to_string())),
+            Diagnostic::new(
+                Severity::Error,
+                DiagnosticKind::MissingGroupEntry,
+                "groups",
+                "dev",
+                "group 'dev' contains 'ghost'".to_string(),
+            )
+            .with_entry("ghost"),
+        ]
+    }
+
+    #[test]
+    fn test_locate_finds_values_and_entries() {
+        let mut diagnostics = diagnostics();
+        locate(&mut diagnostics, SOURCE);
+
+        let span = diagnostics[0].span.unwrap();
+        assert_eq!((span.line, span.column), (2, 6));
+        assert_eq!(&SOURCE[span.start..span.end], "\"githb\"");
+
+        let span = diagnostics[1].span.unwrap();
+        assert_eq!((span.line, span.column), (8, 14));
+        assert_eq!(&SOURCE[span.start..span.end], "\"ghost\"");
+    }
+
+    #[test]
+    fn test_render_points_at_the_line() {
+        let mut diagnostics = diagnostics();
+        locate(&mut diagnostics, SOURCE);
+        let rendered = diagnostics[0].render(Some(Path::new("config.toml")), Some(SOURCE));
+        assert_eq!(
+            rendered,
+            "error: alias 'gh' points to 'githb' which is not in [links]\n \
+             --> config.toml:2:6\n  \
+             |\n\
+             2 | gh = \"githb\"\n  \
+             |      ^^^^^^^\n  \
+             = help: did you mean 'github'?\n"
+        );
+    }
+
+    #[test]
+    fn test_render_without_source() {
+        let rendered = diagnostics()[1].render(None, None);
+        assert_eq!(rendered, "error: group 'dev' contains 'ghost'\n");
+    }
+
+    #[test]
+    fn test_closest() {
+        let names = ["github", "gitlab", "google"];
+        assert_eq!(closest("githb", names), Some("github"));
+        assert_eq!(closest("gogle", names), Some("google"));
+        assert_eq!(closest("bitbucket", names), None);
+    }
+}
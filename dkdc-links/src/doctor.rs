@@ -0,0 +1,270 @@
+//! The full set of config checks behind `dkdc-links doctor`.
+
+use std::collections::HashMap;
+
+use crate::config::{Config, Section};
+use crate::diagnostic::{Diagnostic, DiagnosticKind, Severity};
+use crate::template::{self, Param};
+
+/// Everything [`Config::validate`] reports, plus name collisions, duplicate
+/// and malformed URLs, and unused links.
+pub fn check(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = config.validate();
+    check_collisions(config, &mut diagnostics);
+    check_urls(config, &mut diagnostics);
+    check_unused(config, &mut diagnostics);
+    diagnostics
+}
+
+/// Names defined in more than one section.
+fn check_collisions(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen = Vec::new();
+    for name in config.names() {
+        if seen.contains(&name) {
+            continue;
+        }
+        seen.push(name);
+
+        let sections: Vec<Section> = Section::PRECEDENCE
+            .into_iter()
+            .filter(|section| config.has(*section, name))
+            .collect();
+        let Some((winner, shadowed)) = sections.split_first() else {
+            continue;
+        };
+        for section in shadowed {
+            diagnostics.push(
+                Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::NameCollision,
+                    section.as_str(),
+                    name,
+                    format!("'{name}' is in both [{winner}] and [{section}]; [{winner}] takes precedence"),
+                )
+                .with_suggestion(Some(format!("rename one of the '{name}' entries"))),
+            );
+        }
+    }
+}
+
+/// Links with a malformed or non-http(s) URL, or the same URL as an
+/// earlier link.
+fn check_urls(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let mut first_with_url: HashMap<&str, &str> = HashMap::new();
+    for (name, link) in &config.links {
+        if let Some((severity, problem)) = url_problem(&link.url) {
+            diagnostics.push(Diagnostic::new(
+                severity,
+                DiagnosticKind::InvalidUrl,
+                "links",
+                name,
+                format!("link '{name}' {problem}: {}", link.url),
+            ));
+        }
+
+        let url = link.url.trim_end_matches('/');
+        match first_with_url.get(url) {
+            Some(first) => diagnostics.push(
+                Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::DuplicateUrl,
+                    "links",
+                    name,
+                    format!("link '{name}' has the same URL as '{first}'"),
+                )
+                .with_suggestion(Some(format!(
+                    "remove '{name}' and add it as an alias of '{first}'"
+                ))),
+            ),
+            None => {
+                first_with_url.insert(url, name);
+            }
+        }
+    }
+}
+
+/// What is wrong with `url`, if anything. Template placeholders count as
+/// valid text.
+fn url_problem(url: &str) -> Option<(Severity, &'static str)> {
+    let arity = template::params(url)
+        .iter()
+        .filter(|p| **p != Param::Rest)
+        .count();
+    let url = template::expand("", url, &vec!["x"; arity]).ok()?;
+
+    if url.chars().any(char::is_whitespace) {
+        return Some((Severity::Error, "has whitespace in its URL"));
+    }
+    let Some((scheme, rest)) = url.split_once(':') else {
+        return Some((Severity::Error, "has no URL scheme"));
+    };
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return Some((Severity::Error, "has no URL scheme"));
+    }
+
+    if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") {
+        let host = rest
+            .strip_prefix("//")
+            .map(|r| r.split(['/', '?', '#']).next().unwrap_or_default());
+        if host.is_none_or(str::is_empty) {
+            return Some((Severity::Error, "has no host in its URL"));
+        }
+        None
+    } else {
+        Some((Severity::Warning, "is not an http(s) URL"))
+    }
+}
+
+/// Links that no alias or group refers to.
+fn check_unused(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    for name in config.links.keys() {
+        let used = config.aliases.values().any(|target| target == name)
+            || config.groups.values().flatten().any(|entry| entry == name);
+        if !used {
+            diagnostics.push(Diagnostic::new(
+                Severity::Info,
+                DiagnosticKind::UnusedLink,
+                "links",
+                name,
+                format!("link '{name}' is not used by any alias or group"),
+            ));
+        }
+    }
+}
+
+/// Counts of each severity, e.g. `1 error, 2 warnings`.
+pub fn summary(diagnostics: &[Diagnostic]) -> String {
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    };
+    let parts: Vec<String> = [
+        (Severity::Error, "error"),
+        (Severity::Warning, "warning"),
+        (Severity::Info, "note"),
+    ]
+    .into_iter()
+    .map(|(severity, noun)| (count(severity), noun))
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, noun)| format!("{n} {noun}{}", if n == 1 { "" } else { "s" }))
+    .collect();
+
+    if parts.is_empty() {
+        "no problems found".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(diagnostics: &[Diagnostic]) -> Vec<(DiagnosticKind, &str, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.kind, d.section.as_str(), d.key.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_default_config_is_clean() {
+        let config: Config = toml::from_str(crate::config::DEFAULT_CONFIG).unwrap();
+        assert!(check(&config).is_empty());
+    }
+
+    #[test]
+    fn test_collisions_report_shadowed_entries() {
+        let toml = r#"
+[aliases]
+dev = "rust"
+
+[links]
+rust = "https://rust-lang.org"
+dev = "https://dev.example.com"
+
+[groups]
+dev = ["rust"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let mut diagnostics = check(&config);
+        diagnostics.retain(|d| d.kind == DiagnosticKind::NameCollision);
+        assert_eq!(
+            kinds(&diagnostics),
+            vec![
+                (DiagnosticKind::NameCollision, "aliases", "dev"),
+                (DiagnosticKind::NameCollision, "links", "dev"),
+            ]
+        );
+        assert!(diagnostics[0].message.contains("[groups] takes precedence"));
+    }
+
+    #[test]
+    fn test_url_checks() {
+        let toml = r#"
+[links]
+a = "https://example.com"
+b = "https://example.com/"
+c = "example.com"
+d = "mailto:me@example.com"
+e = "https:///nohost"
+f = "https://example.com/{0}?q={*}"
+g = "https://example.com/a b"
+
+[groups]
+all = ["a", "b", "c", "d", "e", "f", "g"]
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let diagnostics = check(&config);
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.kind, d.key.as_str(), d.severity))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (DiagnosticKind::DuplicateUrl, "b", Severity::Warning),
+                (DiagnosticKind::InvalidUrl, "c", Severity::Error),
+                (DiagnosticKind::InvalidUrl, "d", Severity::Warning),
+                (DiagnosticKind::InvalidUrl, "e", Severity::Error),
+                (DiagnosticKind::InvalidUrl, "g", Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unused_links_are_notes() {
+        let toml = r#"
+[links]
+used = "https://a.example.com"
+lonely = "https://b.example.com"
+
+[aliases]
+u = "used"
+"#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let diagnostics = check(&config);
+        assert_eq!(
+            kinds(&diagnostics),
+            vec![(DiagnosticKind::UnusedLink, "links", "lonely")]
+        );
+        assert_eq!(diagnostics[0].severity, Severity::Info);
+        assert_eq!(summary(&diagnostics), "1 note");
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(summary(&[]), "no problems found");
+        let config: Config = toml::from_str(
+            "[aliases]\nx = \"missing\"\n[links]\na = \"nope\"\n[groups]\ng = [\"a\"]\n",
+        )
+        .unwrap();
+        assert_eq!(summary(&check(&config)), "2 errors");
+    }
+}
@@ -0,0 +1,545 @@
+//! Render a [`Config`] for other tools: a bookmarks file browsers can
+//! import, a Markdown page, a CSV sheet, TSV lines, or JSON. Aliases are
+//! resolved to their link's URL; template URLs are written as they are.
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::config::{unix_seconds, Config, Link};
+
+/// The link `name` resolves to, directly or through an alias.
+fn resolve<'a>(config: &'a Config, name: &str) -> Option<&'a Link> {
+    let name = config.aliases.get(name).map_or(name, String::as_str);
+    config.links.get(name)
+}
+
+/// Groups that no other group contains.
+fn top_level_groups(config: &Config) -> impl Iterator<Item = (&String, &Vec<String>)> {
+    config
+        .groups
+        .iter()
+        .filter(|(name, _)| !config.groups.values().flatten().any(|entry| entry == *name))
+}
+
+// -- Netscape bookmark HTML ---------------------------------------------------
+
+/// A Netscape bookmark file. Top-level groups become folders, nested groups
+/// subfolders, and links reached by no group sit at the root. Aliases appear
+/// only as group entries, as bookmarks named after the alias.
+pub fn netscape(config: &Config) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <!-- This is an automatically generated file.\n     \
+         It will be read and overwritten.\n     \
+         DO NOT EDIT! -->\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n\
+         <DL><p>\n",
+    );
+
+    let mut grouped = HashSet::new();
+    for (group, entries) in top_level_groups(config) {
+        folder(
+            config,
+            group,
+            entries,
+            1,
+            &mut vec![group.as_str()],
+            &mut out,
+        );
+    }
+    for group in config.groups.keys() {
+        for member in config.group_members(group) {
+            grouped.insert(config.aliases.get(member).map_or(member, String::as_str));
+        }
+    }
+    for (name, link) in &config.links {
+        if !grouped.contains(name.as_str()) {
+            bookmark(name, link, 1, &mut out);
+        }
+    }
+    out.push_str("</DL><p>\n");
+    out
+}
+
+fn folder<'a>(
+    config: &'a Config,
+    name: &str,
+    entries: &'a [String],
+    depth: usize,
+    path: &mut Vec<&'a str>,
+    out: &mut String,
+) {
+    let indent = "    ".repeat(depth);
+    out.push_str(&format!(
+        "{indent}<DT><H3>{}</H3>\n{indent}<DL><p>\n",
+        escape(name)
+    ));
+    for entry in entries {
+        if let Some(nested) = config.groups.get(entry) {
+            // A cycle is cut where it would close
+            if !path.contains(&entry.as_str()) {
+                path.push(entry);
+                folder(config, entry, nested, depth + 1, path, out);
+                path.pop();
+            }
+        } else if let Some(link) = resolve(config, entry) {
+            bookmark(entry, link, depth + 1, out);
+        }
+    }
+    out.push_str(&format!("{indent}</DL><p>\n"));
+}
+
+fn bookmark(name: &str, link: &Link, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    let mut attrs = format!("HREF=\"{}\"", escape(&link.url));
+    if let Some(secs) = link.created.as_ref().and_then(unix_seconds) {
+        attrs.push_str(&format!(" ADD_DATE=\"{secs}\""));
+    }
+    if let Some(secs) = link.updated.as_ref().and_then(unix_seconds) {
+        attrs.push_str(&format!(" LAST_MODIFIED=\"{secs}\""));
+    }
+    if !link.tags.is_empty() {
+        attrs.push_str(&format!(" TAGS=\"{}\"", escape(&link.tags.join(","))));
+    }
+    out.push_str(&format!("{indent}<DT><A {attrs}>{}</A>\n", escape(name)));
+    if let Some(description) = &link.description {
+        out.push_str(&format!("{indent}<DD>{}\n", escape(description)));
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// -- Markdown -----------------------------------------------------------------
+
+/// A Markdown page with a list per section and a subsection per group.
+pub fn markdown(config: &Config) -> String {
+    let mut out = String::from("# Links\n");
+
+    if !config.links.is_empty() {
+        out.push('\n');
+        for (name, link) in &config.links {
+            out.push_str(&format!("- {}", md_link(name, &link.url)));
+            if let Some(description) = &link.description {
+                out.push_str(&format!(": {}", md_escape(description)));
+            }
+            if !link.tags.is_empty() {
+                let tags: Vec<String> = link.tags.iter().map(|t| format!("`{t}`")).collect();
+                out.push_str(&format!(" {}", tags.join(" ")));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !config.aliases.is_empty() {
+        out.push_str("\n## Aliases\n\n");
+        for (alias, target) in &config.aliases {
+            let target = match config.links.get(target) {
+                Some(link) => md_link(target, &link.url),
+                None => md_escape(target),
+            };
+            out.push_str(&format!("- {}: {target}\n", md_escape(alias)));
+        }
+    }
+
+    for (group, entries) in &config.groups {
+        out.push_str(&format!("\n## {}\n\n", md_escape(group)));
+        for entry in entries {
+            let item = if config.groups.contains_key(entry) {
+                format!("[{}](#{})", md_escape(entry), anchor(entry))
+            } else {
+                match resolve(config, entry) {
+                    Some(link) => md_link(entry, &link.url),
+                    None => md_escape(entry),
+                }
+            };
+            out.push_str(&format!("- {item}\n"));
+        }
+    }
+    out
+}
+
+fn md_link(text: &str, url: &str) -> String {
+    if url.contains([' ', '(', ')', '<', '>']) {
+        format!("[{}](<{}>)", md_escape(text), url.replace('>', "%3E"))
+    } else {
+        format!("[{}]({url})", md_escape(text))
+    }
+}
+
+fn md_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The anchor most Markdown renderers give a heading.
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+// -- CSV ----------------------------------------------------------------------
+
+const CSV_HEADER: [&str; 8] = [
+    "name",
+    "kind",
+    "url",
+    "target",
+    "description",
+    "tags",
+    "groups",
+    "created",
+];
+
+/// One row per link and alias. Tags and the groups that list the name
+/// directly are separated by `;`.
+pub fn csv(config: &Config) -> String {
+    let mut out = String::new();
+    csv_row(&CSV_HEADER.map(String::from), &mut out);
+
+    let groups_of = |name: &str| -> String {
+        config
+            .groups
+            .iter()
+            .filter(|(_, entries)| entries.iter().any(|e| e == name))
+            .map(|(group, _)| group.as_str())
+            .collect::<Vec<_>>()
+            .join(";")
+    };
+
+    for (name, link) in &config.links {
+        csv_row(
+            &[
+                name.clone(),
+                "link".to_string(),
+                link.url.clone(),
+                String::new(),
+                link.description.clone().unwrap_or_default(),
+                link.tags.join(";"),
+                groups_of(name),
+                link.created.map(|d| d.to_string()).unwrap_or_default(),
+            ],
+            &mut out,
+        );
+    }
+    for (alias, target) in &config.aliases {
+        let link = config.links.get(target);
+        csv_row(
+            &[
+                alias.clone(),
+                "alias".to_string(),
+                link.map(|l| l.url.clone()).unwrap_or_default(),
+                target.clone(),
+                link.and_then(|l| l.description.clone()).unwrap_or_default(),
+                link.map(|l| l.tags.join(";")).unwrap_or_default(),
+                groups_of(alias),
+                String::new(),
+            ],
+            &mut out,
+        );
+    }
+    out
+}
+
+/// Append one RFC 4180 row, quoting fields that need it. A field a
+/// spreadsheet would read as a formula gets a leading `'`, since titles and
+/// URLs often come from imported bookmarks.
+fn csv_row(fields: &[String], out: &mut String) {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                format!("'{field}")
+            } else {
+                field.clone()
+            };
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect();
+    out.push_str(&fields.join(","));
+    out.push_str("\r\n");
+}
+
+// -- TSV ----------------------------------------------------------------------
+
+/// One `kind<TAB>name<TAB>value` line per alias, link and group, in the order
+/// they are listed: an alias's value is its target, a link's its URL, and a
+/// group's its entries separated by `,`. Tabs and line breaks inside values
+/// become spaces so every entry stays on one line.
+pub fn tsv(config: &Config) -> String {
+    let mut out = String::new();
+    let mut row = |kind: &str, name: &str, value: &str| {
+        let fields = [kind, name, value].map(|field| field.replace(['\t', '\n', '\r'], " "));
+        out.push_str(&fields.join("\t"));
+        out.push('\n');
+    };
+    for (alias, target) in &config.aliases {
+        row("alias", alias, target);
+    }
+    for (name, link) in &config.links {
+        row("link", name, &link.url);
+    }
+    for (group, entries) in &config.groups {
+        row("group", group, &entries.join(","));
+    }
+    out
+}
+
+// -- JSON ---------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    links: IndexMap<&'a str, JsonLink<'a>>,
+    aliases: IndexMap<&'a str, JsonAlias<'a>>,
+    groups: IndexMap<&'a str, JsonGroup<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonLink<'a> {
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tags: &'a [String],
+    /// RFC 3339, as datetimes have no JSON type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonAlias<'a> {
+    target: &'a str,
+    url: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonGroup<'a> {
+    entries: &'a [String],
+    /// Every URL the group opens, with nested groups expanded.
+    urls: Vec<&'a str>,
+}
+
+/// The config as a JSON object with `links`, `aliases` and `groups`, each
+/// keyed by name in config order.
+pub fn json(config: &Config) -> Result<String> {
+    let export = JsonExport {
+        links: config
+            .links
+            .iter()
+            .map(|(name, link)| {
+                let json = JsonLink {
+                    url: &link.url,
+                    description: link.description.as_deref(),
+                    tags: &link.tags,
+                    created: link.created.map(|d| d.to_string()),
+                    updated: link.updated.map(|d| d.to_string()),
+                };
+                (name.as_str(), json)
+            })
+            .collect(),
+        aliases: config
+            .aliases
+            .iter()
+            .map(|(alias, target)| {
+                let json = JsonAlias {
+                    target,
+                    url: config.links.get(target).map(|l| l.url.as_str()),
+                };
+                (alias.as_str(), json)
+            })
+            .collect(),
+        groups: config
+            .groups
+            .iter()
+            .map(|(group, entries)| {
+                let urls = config
+                    .group_members(group)
+                    .into_iter()
+                    .filter_map(|member| resolve(config, member))
+                    .map(|link| link.url.as_str())
+                    .collect();
+                (group.as_str(), JsonGroup { entries, urls })
+            })
+            .collect(),
+    };
+    let mut json = serde_json::to_string_pretty(&export).context("Failed to serialize config")?;
+    json.push('\n');
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{self, OnConflict};
+
+    const CONFIG: &str = r#"
+[aliases]
+gh = "github"
+
+[links]
+github = { url = "https://github.com", description = "Code & <stuff>", tags = ["code", "git"], created = 2026-10-17T09:30:12Z }
+docs = "https://docs.rs"
+search = "https://example.com/search?q={*}"
+lonely = "https://example.com/a,b"
+
+[groups]
+dev = ["gh", "rust"]
+rust = ["docs"]
+"#;
+
+    fn config() -> Config {
+        toml::from_str(CONFIG).unwrap()
+    }
+
+    #[test]
+    fn test_netscape() {
+        assert_eq!(
+            netscape(&config()),
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <!-- This is an automatically generated file.\n     \
+             It will be read and overwritten.\n     \
+             DO NOT EDIT! -->\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n\
+             <DL><p>\n    \
+             <DT><H3>dev</H3>\n    \
+             <DL><p>\n        \
+             <DT><A HREF=\"https://github.com\" ADD_DATE=\"1792229412\" TAGS=\"code,git\">gh</A>\n        \
+             <DD>Code &amp; &lt;stuff&gt;\n        \
+             <DT><H3>rust</H3>\n        \
+             <DL><p>\n            \
+             <DT><A HREF=\"https://docs.rs\">docs</A>\n        \
+             </DL><p>\n    \
+             </DL><p>\n    \
+             <DT><A HREF=\"https://example.com/search?q={*}\">search</A>\n    \
+             <DT><A HREF=\"https://example.com/a,b\">lonely</A>\n\
+             </DL><p>\n"
+        );
+    }
+
+    #[test]
+    fn test_netscape_roundtrips_through_import() {
+        let config = config();
+        let root = import::parse_netscape(&netscape(&config)).unwrap();
+        let mut imported = Config::default();
+        import::import(&mut imported, &root, OnConflict::Skip).unwrap();
+
+        assert_eq!(
+            imported.links.keys().collect::<Vec<_>>(),
+            vec!["search", "lonely", "gh", "docs"]
+        );
+        assert_eq!(imported.links["gh"], config.links["github"]);
+        assert_eq!(imported.groups["dev"], vec!["gh", "rust"]);
+        assert_eq!(imported.groups["rust"], vec!["docs"]);
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_eq!(
+            markdown(&config()),
+            "# Links\n\
+             \n\
+             - [github](https://github.com): Code & \\<stuff\\> `code` `git`\n\
+             - [docs](https://docs.rs)\n\
+             - [search](https://example.com/search?q={*})\n\
+             - [lonely](https://example.com/a,b)\n\
+             \n\
+             ## Aliases\n\
+             \n\
+             - gh: [github](https://github.com)\n\
+             \n\
+             ## dev\n\
+             \n\
+             - [gh](https://github.com)\n\
+             - [rust](#rust)\n\
+             \n\
+             ## rust\n\
+             \n\
+             - [docs](https://docs.rs)\n"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            csv(&config()),
+            "name,kind,url,target,description,tags,groups,created\r\n\
+             github,link,https://github.com,,Code & <stuff>,code;git,,2026-10-17T09:30:12Z\r\n\
+             docs,link,https://docs.rs,,,,rust,\r\n\
+             search,link,https://example.com/search?q={*},,,,,\r\n\
+             lonely,link,\"https://example.com/a,b\",,,,,\r\n\
+             gh,alias,https://github.com,github,Code & <stuff>,code;git,dev,\r\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_neutralizes_formulas() {
+        let mut config = Config::default();
+        let mut link = Link::new("https://example.com");
+        link.description = Some("=HYPERLINK(\"https://evil.example\",\"x\")".to_string());
+        link.tags = vec!["+cmd|' /C calc'!A0".to_string(), "@sum".to_string()];
+        config.links.insert("-x".to_string(), link);
+        assert_eq!(
+            csv(&config).lines().nth(1).unwrap(),
+            "'-x,link,https://example.com,,\"'=HYPERLINK(\"\"https://evil.example\"\",\"\"x\"\")\",\
+             '+cmd|' /C calc'!A0;@sum,,"
+        );
+    }
+
+    #[test]
+    fn test_tsv() {
+        let mut config = config();
+        config.links["docs"].url = "https://docs.rs/\tsearch".to_string();
+        assert_eq!(
+            tsv(&config),
+            "alias\tgh\tgithub\n\
+             link\tgithub\thttps://github.com\n\
+             link\tdocs\thttps://docs.rs/ search\n\
+             link\tsearch\thttps://example.com/search?q={*}\n\
+             link\tlonely\thttps://example.com/a,b\n\
+             group\tdev\tgh,rust\n\
+             group\trust\tdocs\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json: serde_json::Value = serde_json::from_str(&json(&config()).unwrap()).unwrap();
+        assert_eq!(json["links"]["github"]["created"], "2026-10-17T09:30:12Z");
+        assert_eq!(json["links"]["github"]["tags"][1], "git");
+        assert!(json["links"]["docs"].get("tags").is_none());
+        assert_eq!(json["aliases"]["gh"]["url"], "https://github.com");
+        assert_eq!(
+            json["groups"]["dev"]["urls"],
+            serde_json::json!(["https://github.com", "https://docs.rs"])
+        );
+    }
+}
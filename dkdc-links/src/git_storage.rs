@@ -0,0 +1,564 @@
+//! TOML storage kept in a git repository. Every change is committed with a
+//! message describing it, and the repository can be synced with a remote.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::config::Config;
+use crate::diagnostic::Diagnostic;
+use crate::journal::describe;
+use crate::storage::{FileLock, Storage, Versioned};
+use crate::toml_storage::TomlStorage;
+
+/// Files dkdc-links keeps next to the config that should not be committed.
+const IGNORED: [&str; 4] = ["*.lock", "*.tmp", "*.undo", "*.backups/"];
+
+/// Git config key that lets dkdc-links commit to a repository it didn't
+/// create, e.g. a dotfiles repo the config lives in.
+const COMMIT_KEY: &str = "dkdc-links.commit";
+
+/// One commit that touched the config, as shown by `dkdc-links history`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commit {
+    /// Abbreviated hash.
+    pub id: String,
+    /// Commit time, e.g. `2026-10-17 09:30`.
+    pub time: String,
+    pub summary: String,
+}
+
+/// A [`TomlStorage`] whose file lives in a git repository, committing after
+/// every save. The repository is created on `init` unless the config is
+/// already inside one; a repository dkdc-links didn't create is only
+/// committed to once [`COMMIT_KEY`] is set in it.
+pub struct GitStorage {
+    inner: TomlStorage,
+    path: PathBuf,
+}
+
+impl GitStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            inner: TomlStorage::new(path.clone()),
+            path,
+        }
+    }
+
+    pub fn with_default_path() -> Result<Self> {
+        Ok(Self::new(TomlStorage::default_path()?))
+    }
+
+    fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    fn file_name(&self) -> &str {
+        self.path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+    }
+
+    fn run(&self, args: &[&str]) -> Result<Output> {
+        Command::new("git")
+            .arg("-C")
+            .arg(self.dir())
+            .args(args)
+            .output()
+            .context("Failed to run git; is it installed?")
+    }
+
+    /// Run git in the config's directory, returning its trimmed stdout.
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = self.run(args)?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Whether a git command succeeds, for commands that answer yes or no
+    /// through their exit status.
+    fn git_succeeds(&self, args: &[&str]) -> Result<bool> {
+        Ok(self.run(args)?.status.success())
+    }
+
+    /// Commit after a save. The change is on disk by then, so a failed commit
+    /// is reported instead of returned; the next commit picks the change up.
+    fn commit_saved(&self, message: &str) {
+        if let Err(e) = self.commit(message) {
+            eprintln!(
+                "warning: saved {} but could not commit it: {e:#}",
+                self.path.display()
+            );
+        }
+    }
+
+    /// Whether the repository holding the config was made for it: its top
+    /// level is the config's own directory, as when `init` created it.
+    fn owns_repository(&self) -> Result<bool> {
+        let top = self.git(&["rev-parse", "--show-toplevel"])?;
+        let same = |a: &Path, b: &Path| match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        };
+        Ok(same(Path::new(&top), self.dir()))
+    }
+
+    /// Commit the config file alone, if it has changed since the last commit.
+    fn commit(&self, message: &str) -> Result<()> {
+        let file = self.file_name();
+        self.git(&["add", "--", file])?;
+        if self.git_succeeds(&["diff", "--cached", "--quiet", "--", file])? {
+            return Ok(());
+        }
+        let message = if message.is_empty() {
+            "update config"
+        } else {
+            message
+        };
+        self.git(&["commit", "--quiet", "--message", message, "--", file])
+            .map(drop)
+    }
+
+    /// Add the patterns in [`IGNORED`] to the `.gitignore` next to the config,
+    /// returning whether it changed.
+    fn ignore_sidecars(&self) -> Result<bool> {
+        let path = self.dir().join(".gitignore");
+        let mut contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e).context("Failed to read .gitignore"),
+        };
+        let missing: Vec<&str> = IGNORED
+            .into_iter()
+            .filter(|pattern| !contents.lines().any(|line| line.trim() == *pattern))
+            .collect();
+        if missing.is_empty() {
+            return Ok(false);
+        }
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        for pattern in missing {
+            contents.push_str(pattern);
+            contents.push('\n');
+        }
+        fs::write(&path, contents).context("Failed to write .gitignore")?;
+        Ok(true)
+    }
+
+    /// The remote to sync with: the current branch's upstream remote,
+    /// otherwise `origin`, otherwise the only remote there is.
+    fn remote(&self, branch: &str) -> Result<String> {
+        let key = format!("branch.{branch}.remote");
+        if let Ok(remote) = self.git(&["config", "--get", &key]) {
+            return Ok(remote);
+        }
+        let remotes = self.git(&["remote"])?;
+        let remotes: Vec<&str> = remotes.lines().collect();
+        match remotes.as_slice() {
+            _ if remotes.contains(&"origin") => Ok("origin".to_string()),
+            [remote] => Ok(remote.to_string()),
+            _ => anyhow::bail!(
+                "no git remote to sync with; add one with `git -C {} remote add origin <url>`",
+                self.dir().display()
+            ),
+        }
+    }
+
+    fn count(&self, range: &str) -> Result<usize> {
+        self.git(&["rev-list", "--count", range])?
+            .parse()
+            .context("Failed to count commits")
+    }
+}
+
+impl Storage for GitStorage {
+    fn load(&self) -> Result<Config> {
+        self.inner.load()
+    }
+
+    fn save(&self, config: &Config) -> Result<()> {
+        let _lock = self.lock()?;
+        let before = self.inner.stored().unwrap_or_default();
+        self.inner.save(config)?;
+        self.commit_saved(&describe(&before, config)?);
+        Ok(())
+    }
+
+    fn update(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        let _lock = self.lock()?;
+        let mut message = String::new();
+        self.inner.update(&mut |config| {
+            let before = config.clone();
+            f(config)?;
+            message = describe(&before, config)?;
+            Ok(())
+        })?;
+        self.commit_saved(&message);
+        Ok(())
+    }
+
+    /// Creates the repository if the config isn't in one. In a repository
+    /// dkdc-links made, also ignores the sidecar files and starts tracking
+    /// the config. Any other repository is left alone, and unless it has
+    /// opted in with [`COMMIT_KEY`] this fails rather than commit to it.
+    fn init(&self) -> Result<()> {
+        self.inner.init()?;
+        if !self.git_succeeds(&["rev-parse", "--git-dir"])? {
+            self.git(&["init", "--quiet"])?;
+        }
+        let file = self.file_name();
+        if !self.owns_repository()? {
+            if !self.git_succeeds(&["config", "--bool", "--get", COMMIT_KEY])? {
+                let top = self.git(&["rev-parse", "--show-toplevel"])?;
+                anyhow::bail!(
+                    "{} is inside the git repository {top}, which dkdc-links didn't create; \
+                     to commit the config there, run `git -C {top} config {COMMIT_KEY} true`",
+                    self.path.display()
+                );
+            }
+            if !self.git_succeeds(&["ls-files", "--error-unmatch", "--", file])? {
+                self.commit_saved("track dkdc-links config");
+            }
+            return Ok(());
+        }
+        let ignore_changed = self.ignore_sidecars()?;
+        let tracked = self.git_succeeds(&["ls-files", "--error-unmatch", "--", file])?;
+        if ignore_changed || !tracked {
+            let committed = self.git(&["add", "--", ".gitignore", file]).and_then(|_| {
+                self.git(&[
+                    "commit",
+                    "--quiet",
+                    "--message",
+                    "track dkdc-links config",
+                    "--",
+                    ".gitignore",
+                    file,
+                ])
+            });
+            if let Err(e) = committed {
+                eprintln!("warning: could not commit {}: {e:#}", self.path.display());
+            }
+        }
+        Ok(())
+    }
+
+    fn backend_name(&self) -> &str {
+        "git"
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn stored(&self) -> Result<Config> {
+        self.inner.stored()
+    }
+
+    fn lock(&self) -> Result<Option<FileLock>> {
+        self.inner.lock()
+    }
+
+    fn diagnostics(&self) -> Result<Vec<Diagnostic>> {
+        self.inner.diagnostics()
+    }
+
+    fn versioned(&self) -> Option<&dyn Versioned> {
+        Some(self)
+    }
+}
+
+impl Versioned for GitStorage {
+    fn update_as(&self, message: &str, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        let _lock = self.lock()?;
+        self.inner.update(f)?;
+        self.commit_saved(message);
+        Ok(())
+    }
+
+    fn history(&self, limit: usize) -> Result<Vec<Commit>> {
+        let limit = limit.to_string();
+        let log = self.git(&[
+            "log",
+            "--max-count",
+            &limit,
+            "--date=format:%Y-%m-%d %H:%M",
+            "--format=%h%x09%ad%x09%s",
+            "--",
+            self.file_name(),
+        ])?;
+        Ok(log
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(Commit {
+                    id: fields.next()?.to_string(),
+                    time: fields.next()?.to_string(),
+                    summary: fields.next()?.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// Commit any hand edits, rebase onto the remote branch, then push.
+    fn sync(&self) -> Result<String> {
+        self.commit("edit config by hand")?;
+        let branch = self.git(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+        let remote = self.remote(&branch)?;
+        self.git(&["fetch", "--quiet", &remote])?;
+
+        let upstream = format!("{remote}/{branch}");
+        let has_upstream = self.git_succeeds(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/remotes/{upstream}"),
+        ])?;
+        let (pulled, pushed) = if has_upstream {
+            let pulled = self.count(&format!("HEAD..{upstream}"))?;
+            if let Err(e) = self.git(&["rebase", "--quiet", "--autostash", &upstream]) {
+                let _ = self.git(&["rebase", "--abort"]);
+                return Err(e).context(format!(
+                    "Local changes conflict with {upstream}; nothing was synced. \
+                     Resolve it with git in {}",
+                    self.dir().display()
+                ));
+            }
+            (pulled, self.count(&format!("{upstream}..HEAD"))?)
+        } else {
+            (0, self.count("HEAD")?)
+        };
+        self.git(&["push", "--quiet", "--set-upstream", &remote, &branch])?;
+
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        Ok(format!(
+            "synced with {upstream}: pulled {pulled} change{}, pushed {pushed} change{}",
+            plural(pulled),
+            plural(pushed)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Run git in `dir` for test setup.
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {args:?}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// Set a committer identity so tests don't depend on global git config.
+    fn identify(dir: &Path) {
+        git(dir, &["config", "user.name", "Test"]);
+        git(dir, &["config", "user.email", "test@example.com"]);
+        git(dir, &["config", "commit.gpgsign", "false"]);
+    }
+
+    /// A config in a fresh repository, initialized through the storage.
+    fn storage(dir: &Path) -> GitStorage {
+        fs::create_dir_all(dir).unwrap();
+        git(dir, &["init", "--quiet", "--initial-branch=main"]);
+        identify(dir);
+        let storage = GitStorage::new(dir.join("config.toml"));
+        storage.init().unwrap();
+        storage
+    }
+
+    fn summaries(storage: &dyn Storage) -> Vec<String> {
+        storage
+            .versioned()
+            .unwrap()
+            .history(10)
+            .unwrap()
+            .into_iter()
+            .map(|commit| commit.summary)
+            .collect()
+    }
+
+    #[test]
+    fn test_commits_each_change() {
+        let dir = TempDir::new().unwrap();
+        let storage = storage(dir.path());
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        storage.rename_alias("a2", "two").unwrap();
+        // Saving an unchanged config adds no commit
+        storage.save(&storage.load().unwrap()).unwrap();
+
+        assert_eq!(
+            summaries(&storage),
+            vec![
+                "rename alias a2 -> two",
+                "add link rust",
+                "track dkdc-links config",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sidecar_files_are_ignored() {
+        let dir = TempDir::new().unwrap();
+        let storage = crate::journal::JournaledStorage::new(Box::new(storage(dir.path())));
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        storage.undo().unwrap();
+
+        assert!(dir.path().join("config.toml.undo").exists());
+        assert!(dir.path().join("config.toml.backups").exists());
+        assert_eq!(git(dir.path(), &["status", "--porcelain"]), "");
+        assert_eq!(summaries(&storage)[0], "undo: add link rust");
+    }
+
+    #[test]
+    fn test_init_leaves_an_enclosing_repository_alone() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "--quiet"]);
+        identify(dir.path());
+        let links = dir.path().join("links");
+        fs::create_dir_all(&links).unwrap();
+        let storage = GitStorage::new(links.join("config.toml"));
+
+        let err = storage.init().unwrap_err().to_string();
+        assert!(err.contains("config dkdc-links.commit true"), "{err}");
+        assert!(!links.join(".git").exists());
+        assert_eq!(git(dir.path(), &["ls-files"]), "");
+
+        // Once the repository opts in, only the config itself is committed
+        git(dir.path(), &["config", COMMIT_KEY, "true"]);
+        storage.init().unwrap();
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        assert!(!links.join(".gitignore").exists());
+        assert_eq!(git(dir.path(), &["ls-files"]), "links/config.toml");
+        assert_eq!(
+            summaries(&storage),
+            vec!["add link rust", "track dkdc-links config"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_commit_keeps_the_save() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let storage = storage(dir.path());
+        let hooks = dir.path().join(".git").join("hooks");
+        fs::write(hooks.join("pre-commit"), "#!/bin/sh\nexit 1\n").unwrap();
+        fs::set_permissions(hooks.join("pre-commit"), fs::Permissions::from_mode(0o755)).unwrap();
+
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        assert!(storage.load().unwrap().links.contains_key("rust"));
+        assert_eq!(summaries(&storage), vec!["track dkdc-links config"]);
+    }
+
+    #[test]
+    fn test_sync_with_a_bare_remote() {
+        let dir = TempDir::new().unwrap();
+        let remote = dir.path().join("remote.git");
+        git(
+            dir.path(),
+            &[
+                "init",
+                "--quiet",
+                "--bare",
+                "--initial-branch=main",
+                "remote.git",
+            ],
+        );
+
+        let laptop = storage(&dir.path().join("laptop"));
+        git(
+            &dir.path().join("laptop"),
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+        assert_eq!(
+            laptop.sync().unwrap(),
+            "synced with origin/main: pulled 0 changes, pushed 1 change"
+        );
+
+        git(dir.path(), &["clone", "--quiet", "remote.git", "desktop"]);
+        identify(&dir.path().join("desktop"));
+        let desktop = GitStorage::new(dir.path().join("desktop/config.toml"));
+        desktop.init().unwrap();
+        desktop.put_alias("gh", "link2").unwrap();
+        desktop.sync().unwrap();
+
+        laptop.put_link("rust", "https://rust-lang.org").unwrap();
+        assert_eq!(
+            laptop.sync().unwrap(),
+            "synced with origin/main: pulled 1 change, pushed 1 change"
+        );
+        let config = laptop.load().unwrap();
+        assert_eq!(config.aliases["gh"], "link2");
+        assert!(config.links.contains_key("rust"));
+    }
+
+    #[test]
+    fn test_sync_stops_on_conflict() {
+        let dir = TempDir::new().unwrap();
+        git(
+            dir.path(),
+            &[
+                "init",
+                "--quiet",
+                "--bare",
+                "--initial-branch=main",
+                "remote.git",
+            ],
+        );
+        let remote = dir.path().join("remote.git");
+
+        let laptop = storage(&dir.path().join("laptop"));
+        git(
+            &dir.path().join("laptop"),
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+        laptop.sync().unwrap();
+
+        git(dir.path(), &["clone", "--quiet", "remote.git", "desktop"]);
+        identify(&dir.path().join("desktop"));
+        let desktop = GitStorage::new(dir.path().join("desktop/config.toml"));
+        desktop
+            .put_link("link1", "https://desktop.example.com")
+            .unwrap();
+        desktop.sync().unwrap();
+
+        laptop
+            .put_link("link1", "https://laptop.example.com")
+            .unwrap();
+        let err = laptop.sync().unwrap_err().to_string();
+        assert!(err.contains("conflict with origin/main"), "{err}");
+        assert_eq!(
+            laptop.load().unwrap().links["link1"].url,
+            "https://laptop.example.com"
+        );
+        assert!(!dir.path().join("laptop/.git/rebase-merge").exists());
+    }
+
+    #[test]
+    fn test_sync_without_remote_errors() {
+        let dir = TempDir::new().unwrap();
+        let err = storage(dir.path()).sync().unwrap_err().to_string();
+        assert!(err.contains("no git remote to sync with"));
+    }
+}
@@ -0,0 +1,1130 @@
+//! Import browser bookmarks into a [`Config`]. Bookmarks become links named
+//! after their titles, and folders become groups of what they contain.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use toml::value::Datetime;
+
+use crate::config::{utc, Config, DeleteMode, Link};
+
+/// Longest name derived from a title, in characters.
+const MAX_NAME_LEN: usize = 40;
+
+/// One bookmark, as read from a browser.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub added: Option<Datetime>,
+}
+
+/// A bookmark folder. The root of an import is a folder with no name.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Folder {
+    pub name: String,
+    pub bookmarks: Vec<Bookmark>,
+    pub folders: Vec<Folder>,
+}
+
+impl Folder {
+    /// The folder at `path`, e.g. `Bookmarks Toolbar/Dev`, comparing names
+    /// case-insensitively. The first part may name a folder at any depth.
+    pub fn find(&self, path: &str) -> Option<&Folder> {
+        let mut parts = path.split('/').map(str::trim).filter(|p| !p.is_empty());
+        let mut folder = self.find_named(parts.next()?)?;
+        for part in parts {
+            folder = folder
+                .folders
+                .iter()
+                .find(|f| f.name.eq_ignore_ascii_case(part))?;
+        }
+        Some(folder)
+    }
+
+    fn find_named(&self, name: &str) -> Option<&Folder> {
+        self.folders.iter().find_map(|f| {
+            if f.name.eq_ignore_ascii_case(name) {
+                Some(f)
+            } else {
+                f.find_named(name)
+            }
+        })
+    }
+
+    /// Bookmarks in this folder and all folders below it.
+    pub fn len(&self) -> usize {
+        self.bookmarks.len() + self.folders.iter().map(Folder::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// What to do when an imported name is already in the config.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OnConflict {
+    /// Keep the existing entry and leave the bookmark out
+    #[default]
+    Skip,
+    /// Replace the existing entry
+    Overwrite,
+    /// Import under a new name, e.g. `github-2`
+    Rename,
+}
+
+/// How one name conflict was resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Skipped,
+    Overwritten,
+    Renamed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    /// `link`, `alias` or `group`: what the name already was.
+    pub existing: &'static str,
+    pub resolution: Resolution,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = &self.name;
+        let existing = match self.existing {
+            "alias" => "an alias",
+            "link" => "a link",
+            _ => "a group",
+        };
+        match &self.resolution {
+            Resolution::Skipped => write!(f, "skipped '{name}': already {existing}"),
+            Resolution::Overwritten => write!(f, "replaced '{name}', which was {existing}"),
+            Resolution::Renamed(new) => {
+                write!(
+                    f,
+                    "imported '{name}' as '{new}': '{name}' is already {existing}"
+                )
+            }
+        }
+    }
+}
+
+/// What an import changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    /// Links added or replaced.
+    pub links: Vec<String>,
+    /// Groups added or extended.
+    pub groups: Vec<String>,
+    /// Bookmarks whose URL was already a link, by that link's name.
+    pub existing: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Report {
+    /// One-line summary, e.g. `imported 12 links and 3 groups`.
+    pub fn summary(&self) -> String {
+        let count = |n: usize, noun: &str| format!("{n} {noun}{}", if n == 1 { "" } else { "s" });
+        let mut summary = format!(
+            "imported {} and {}",
+            count(self.links.len(), "link"),
+            count(self.groups.len(), "group")
+        );
+        if !self.existing.is_empty() {
+            summary.push_str(&format!(
+                "; {} already saved",
+                count(self.existing.len(), "bookmark")
+            ));
+        }
+        summary
+    }
+}
+
+/// Add the bookmarks and folders under `root` to `config`. Folders become
+/// groups of their bookmarks and subfolders; bookmarks at the root join no
+/// group. A bookmark whose URL is already a link reuses that link.
+pub fn import(config: &mut Config, root: &Folder, on_conflict: OnConflict) -> Result<Report> {
+    let mut importer = Importer {
+        config,
+        on_conflict,
+        taken: HashSet::new(),
+        report: Report::default(),
+    };
+    importer.folder(root)?;
+    Ok(importer.report)
+}
+
+struct Importer<'a> {
+    config: &'a mut Config,
+    on_conflict: OnConflict,
+    /// Names this import has already used, which it never overwrites.
+    taken: HashSet<String>,
+    report: Report,
+}
+
+impl Importer<'_> {
+    /// Import a folder's contents, returning the names its group should hold.
+    fn folder(&mut self, folder: &Folder) -> Result<Vec<String>> {
+        let mut entries = Vec::new();
+        for bookmark in &folder.bookmarks {
+            if let Some(name) = self.bookmark(bookmark)? {
+                entries.push(name);
+            }
+        }
+        for child in &folder.folders {
+            let child_entries = self.folder(child)?;
+            if let Some(name) = self.group(&child.name, child_entries)? {
+                entries.push(name);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn bookmark(&mut self, bookmark: &Bookmark) -> Result<Option<String>> {
+        if let Some((name, _)) = self
+            .config
+            .links
+            .iter()
+            .find(|(_, l)| l.url == bookmark.url)
+        {
+            let name = name.clone();
+            if !self.report.links.contains(&name) {
+                self.report.existing.push(name.clone());
+            }
+            return Ok(Some(name));
+        }
+
+        let Some(name) = self.claim(&link_name(bookmark))? else {
+            return Ok(None);
+        };
+        let link = Link {
+            url: bookmark.url.clone(),
+            description: bookmark.description.clone(),
+            tags: bookmark.tags.clone(),
+            created: bookmark.added,
+            updated: None,
+        };
+        self.config.put_link(&name, link)?;
+        self.report.links.push(name.clone());
+        Ok(Some(name))
+    }
+
+    fn group(&mut self, title: &str, entries: Vec<String>) -> Result<Option<String>> {
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        let slug = Some(slug(title))
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "folder".to_string());
+
+        // Importing into an existing group adds to it
+        let name = if self.config.groups.contains_key(&slug) && !self.taken.contains(&slug) {
+            self.taken.insert(slug.clone());
+            slug
+        } else {
+            match self.claim(&slug)? {
+                Some(name) => name,
+                None => return Ok(None),
+            }
+        };
+        let before = self.config.groups.get(&name).cloned();
+        self.config.add_to_group(&name, &entries)?;
+        if before.as_ref() != self.config.groups.get(&name) {
+            self.report.groups.push(name.clone());
+        }
+        Ok(Some(name))
+    }
+
+    /// A free name for a new entry, based on `name`. Resolves a clash with
+    /// the existing config per [`OnConflict`], and a clash with this import
+    /// by renaming. `None` means the entry should be skipped.
+    fn claim(&mut self, name: &str) -> Result<Option<String>> {
+        let name = if self.taken.contains(name) {
+            self.unique(name)
+        } else if let Some(section) = self.config.section_of(name) {
+            let existing = section.kind();
+            let resolution = match self.on_conflict {
+                OnConflict::Skip => Resolution::Skipped,
+                OnConflict::Overwrite => {
+                    // References to the old entry now reach the new one
+                    self.config.delete(name, DeleteMode::Orphan)?;
+                    Resolution::Overwritten
+                }
+                OnConflict::Rename => Resolution::Renamed(self.unique(name)),
+            };
+            self.report.conflicts.push(Conflict {
+                name: name.to_string(),
+                existing,
+                resolution: resolution.clone(),
+            });
+            match resolution {
+                Resolution::Skipped => return Ok(None),
+                Resolution::Overwritten => name.to_string(),
+                Resolution::Renamed(new) => new,
+            }
+        } else {
+            name.to_string()
+        };
+        self.taken.insert(name.clone());
+        Ok(Some(name))
+    }
+
+    /// `name-2`, `name-3`, ... whichever is free first.
+    fn unique(&self, name: &str) -> String {
+        (2..)
+            .map(|n| format!("{name}-{n}"))
+            .find(|candidate| !self.config.contains(candidate) && !self.taken.contains(candidate))
+            .expect("some suffix is free")
+    }
+}
+
+/// A link name for a bookmark: its title as a slug, or its host if the
+/// title has nothing usable.
+fn link_name(bookmark: &Bookmark) -> String {
+    let name = slug(&bookmark.title);
+    if !name.is_empty() {
+        return name;
+    }
+    let host = bookmark
+        .url
+        .split_once("://")
+        .map_or(bookmark.url.as_str(), |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    Some(slug(host.trim_start_matches("www.")))
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "link".to_string())
+}
+
+/// Lowercase words joined by hyphens, e.g. `The Rust Book` -> `the-rust-book`,
+/// cut at a word boundary to at most [`MAX_NAME_LEN`] characters.
+pub fn slug(text: &str) -> String {
+    let mut slug = String::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let word = word.to_lowercase();
+        let len = slug.chars().count();
+        if len > 0 && len + 1 + word.chars().count() > MAX_NAME_LEN {
+            break;
+        }
+        if len > 0 {
+            slug.push('-');
+        }
+        slug.extend(word.chars().take(MAX_NAME_LEN));
+    }
+    slug
+}
+
+// -- Netscape bookmark HTML ---------------------------------------------------
+
+/// Parse the Netscape bookmark file every browser exports. Folders are
+/// `<H3>` headings followed by a `<DL>` list; bookmarks are `<A>` tags,
+/// optionally followed by a `<DD>` description. Bookmarklets and Firefox
+/// `place:` queries are left out.
+pub fn parse_netscape(html: &str) -> Result<Folder> {
+    let doctype = "<!doctype netscape-bookmark-file";
+    if find_ignore_case(html, doctype).is_none() {
+        anyhow::bail!("not a Netscape bookmark file (expected {doctype}-1>)");
+    }
+
+    // Folders still open, outermost first
+    let mut open: Vec<Folder> = Vec::new();
+    let mut root = None;
+    // Name from an <H3> whose <DL> hasn't started yet
+    let mut heading: Option<String> = None;
+    // Whether a <DD> now would describe the last bookmark
+    let mut describes_bookmark = false;
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = tag_end(rest).or_else(|| rest.find('>')) else {
+            break;
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+        let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+
+        match name.to_ascii_lowercase().as_str() {
+            "h3" => {
+                let (text, after) = text_until(rest, "</h3");
+                heading = Some(decode(text).trim().to_string());
+                rest = after;
+                describes_bookmark = false;
+            }
+            "dl" => {
+                open.push(Folder {
+                    name: heading.take().unwrap_or_default(),
+                    ..Folder::default()
+                });
+                describes_bookmark = false;
+            }
+            "/dl" => {
+                if let Some(folder) = open.pop() {
+                    close_folder(folder, &mut open, &mut root);
+                }
+                describes_bookmark = false;
+            }
+            "a" => {
+                let (text, after) = text_until(rest, "</a");
+                rest = after;
+                let (Some(url), Some(folder)) = (attr(attrs, "href"), open.last_mut()) else {
+                    continue;
+                };
+                if !importable(&url) {
+                    describes_bookmark = false;
+                    continue;
+                }
+                folder.bookmarks.push(Bookmark {
+                    title: decode(text).trim().to_string(),
+                    url,
+                    description: None,
+                    tags: attr(attrs, "tags")
+                        .map(|tags| {
+                            tags.split(',')
+                                .map(str::trim)
+                                .filter(|t| !t.is_empty())
+                                .map(String::from)
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    added: attr(attrs, "add_date")
+                        .and_then(|secs| secs.parse().ok())
+                        .filter(|secs| *secs > 0)
+                        .map(utc),
+                });
+                describes_bookmark = true;
+            }
+            "dd" => {
+                let text = decode(&rest[..rest.find('<').unwrap_or(rest.len())]);
+                let bookmark = open.last_mut().and_then(|f| f.bookmarks.last_mut());
+                if let (true, Some(bookmark)) = (describes_bookmark, bookmark) {
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        bookmark.description = Some(text.to_string());
+                    }
+                }
+                describes_bookmark = false;
+            }
+            _ => {}
+        }
+    }
+
+    // Tolerate a file cut off before its closing tags
+    while let Some(folder) = open.pop() {
+        close_folder(folder, &mut open, &mut root);
+    }
+    Ok(root.unwrap_or_default())
+}
+
+/// Whether a URL is worth a link: not empty, a bookmarklet or a Firefox
+/// `place:` query.
+fn importable(url: &str) -> bool {
+    !(url.is_empty() || url.starts_with("place:") || url.starts_with("javascript:"))
+}
+
+/// Attach a finished folder to its parent, or make it the root. A list
+/// without a heading is merged into its parent.
+fn close_folder(folder: Folder, open: &mut [Folder], root: &mut Option<Folder>) {
+    match open.last_mut() {
+        Some(parent) if folder.name.is_empty() => {
+            parent.bookmarks.extend(folder.bookmarks);
+            parent.folders.extend(folder.folders);
+        }
+        Some(parent) => parent.folders.push(folder),
+        None => match root {
+            Some(root) => {
+                root.bookmarks.extend(folder.bookmarks);
+                root.folders.extend(folder.folders);
+            }
+            None => *root = Some(folder),
+        },
+    }
+}
+
+/// The text before the closing tag `close` (e.g. `</a`), and what follows
+/// that tag.
+fn text_until<'a>(html: &'a str, close: &str) -> (&'a str, &'a str) {
+    match find_ignore_case(html, close) {
+        Some(i) => {
+            let after = &html[i..];
+            let end = after.find('>').map_or(after.len(), |j| j + 1);
+            (&html[..i], &after[end..])
+        }
+        None => (html, ""),
+    }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack.match_indices('<').map(|(i, _)| i).find(|&i| {
+        haystack
+            .get(i..i + needle.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(needle))
+    })
+}
+
+/// The decoded value of attribute `name` in a tag's attribute text.
+/// Where the tag starting `html` ends: the first `>` outside a quoted
+/// attribute value, so `HREF="/?a>b"` doesn't cut it short.
+fn tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut after_equals = false;
+    for (i, c) in html.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '>' => return Some(i),
+            None if after_equals && (c == '"' || c == '\'') => quote = Some(c),
+            None => {}
+        }
+        if !c.is_whitespace() {
+            after_equals = quote.is_none() && c == '=';
+        }
+    }
+    None
+}
+
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs.trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+
+        let mut value = "";
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    let end = inner.find(quote).unwrap_or(inner.len());
+                    value = &inner[..end];
+                    rest = inner.get(end + 1..).unwrap_or_default();
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    value = &after[..end];
+                    rest = &after[end..];
+                }
+            }
+        }
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode(value));
+        }
+        rest = rest.trim_start();
+    }
+    None
+}
+
+/// Replace HTML character references like `&amp;` and `&#39;`.
+fn decode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = entity.strip_prefix('#')?;
+                    let code = match code.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => code.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// -- Chromium Bookmarks JSON --------------------------------------------------
+
+/// Name of the bookmarks file in a Chromium profile directory.
+pub const CHROMIUM_FILE: &str = "Bookmarks";
+
+/// Seconds from 1601-01-01, where Chromium counts from, to the Unix epoch.
+const WINDOWS_EPOCH_OFFSET: u64 = 11_644_473_600;
+
+#[derive(Deserialize)]
+struct ChromiumFile {
+    roots: IndexMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChromiumNode {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    url: Option<String>,
+    /// Microseconds since 1601-01-01, as a string
+    date_added: Option<String>,
+    #[serde(default)]
+    children: Vec<ChromiumNode>,
+}
+
+/// Parse the `Bookmarks` file of a Chromium profile (Chrome, Edge, Brave, …).
+/// Each root, such as the bookmarks bar, becomes a top-level folder.
+pub fn parse_chromium(json: &str) -> Result<Folder> {
+    let file: ChromiumFile = serde_json::from_str(json).context("not a Chromium Bookmarks file")?;
+    let mut root = Folder::default();
+    for node in file.roots.into_values() {
+        // Older files keep sync metadata next to the roots
+        if let Ok(node) = serde_json::from_value::<ChromiumNode>(node) {
+            if node.kind == "folder" {
+                root.folders.push(chromium_folder(node));
+            }
+        }
+    }
+    Ok(root)
+}
+
+fn chromium_folder(node: ChromiumNode) -> Folder {
+    let mut folder = Folder {
+        name: node.name,
+        ..Folder::default()
+    };
+    for child in node.children {
+        if child.kind == "folder" {
+            folder.folders.push(chromium_folder(child));
+            continue;
+        }
+        match child.url {
+            Some(url) if child.kind == "url" && importable(&url) => {
+                folder.bookmarks.push(Bookmark {
+                    title: child.name,
+                    url,
+                    added: child
+                        .date_added
+                        .and_then(|micros| micros.parse::<u64>().ok())
+                        .and_then(|micros| (micros / 1_000_000).checked_sub(WINDOWS_EPOCH_OFFSET))
+                        .filter(|secs| *secs > 0)
+                        .map(utc),
+                    ..Bookmark::default()
+                })
+            }
+            _ => {}
+        }
+    }
+    folder
+}
+
+// -- Firefox places.sqlite ----------------------------------------------------
+
+/// Name of the bookmarks database in a Firefox profile directory.
+pub const FIREFOX_FILE: &str = "places.sqlite";
+
+/// Read the bookmarks in a Firefox `places.sqlite` database. The menu,
+/// toolbar, other and mobile roots become top-level folders, and Firefox
+/// tags become bookmark tags.
+#[cfg(feature = "sqlite")]
+pub fn read_firefox(path: &Path) -> Result<Folder> {
+    use rusqlite::{Connection, OpenFlags};
+    use std::collections::HashMap;
+
+    const FOLDER: i64 = 2;
+    const ROOTS: [(&str, &str); 4] = [
+        ("menu________", "Bookmarks Menu"),
+        ("toolbar_____", "Bookmarks Toolbar"),
+        ("unfiled_____", "Other Bookmarks"),
+        ("mobile______", "Mobile Bookmarks"),
+    ];
+
+    struct Row {
+        id: i64,
+        kind: i64,
+        parent: i64,
+        title: String,
+        added: Option<i64>,
+        url: Option<String>,
+        guid: String,
+    }
+
+    if !path.is_file() {
+        anyhow::bail!("no Firefox bookmarks at {}", path.display());
+    }
+    let read = || -> rusqlite::Result<Vec<Row>> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.type, b.parent, b.title, b.dateAdded, p.url, b.guid
+             FROM moz_bookmarks b LEFT JOIN moz_places p ON p.id = b.fk
+             ORDER BY b.parent, b.position",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Row {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                parent: row.get(2)?,
+                title: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                added: row.get(4)?,
+                url: row.get(5)?,
+                guid: row.get(6)?,
+            })
+        })?;
+        rows.collect()
+    };
+    let rows = read().with_context(|| {
+        format!(
+            "Failed to read Firefox bookmarks from {} (if Firefox is running, close it or copy the file first)",
+            path.display()
+        )
+    })?;
+
+    type Children<'a> = HashMap<i64, Vec<&'a Row>>;
+    let mut children: Children = HashMap::new();
+    for row in &rows {
+        children.entry(row.parent).or_default().push(row);
+    }
+    fn children_of<'a>(children: &'a Children<'a>, id: i64) -> &'a [&'a Row] {
+        children.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    // Tags are folders under the tags root holding a bookmark per tagged URL
+    let mut tags: HashMap<&str, Vec<String>> = HashMap::new();
+    if let Some(tags_root) = rows.iter().find(|r| r.guid == "tags________") {
+        for tag in children_of(&children, tags_root.id) {
+            for tagged in children_of(&children, tag.id) {
+                if let Some(url) = &tagged.url {
+                    tags.entry(url).or_default().push(tag.title.clone());
+                }
+            }
+        }
+    }
+
+    fn build(
+        row: &Row,
+        name: String,
+        children: &Children,
+        tags: &HashMap<&str, Vec<String>>,
+    ) -> Folder {
+        let mut folder = Folder {
+            name,
+            ..Folder::default()
+        };
+        for child in children_of(children, row.id) {
+            match (child.kind, &child.url) {
+                (FOLDER, _) => {
+                    folder
+                        .folders
+                        .push(build(child, child.title.clone(), children, tags))
+                }
+                (_, Some(url)) if importable(url) => folder.bookmarks.push(Bookmark {
+                    title: child.title.clone(),
+                    url: url.clone(),
+                    tags: tags.get(url.as_str()).cloned().unwrap_or_default(),
+                    added: child
+                        .added
+                        .and_then(|micros| u64::try_from(micros / 1_000_000).ok())
+                        .filter(|secs| *secs > 0)
+                        .map(utc),
+                    ..Bookmark::default()
+                }),
+                _ => {}
+            }
+        }
+        folder
+    }
+
+    let mut root = Folder::default();
+    for (guid, name) in ROOTS {
+        if let Some(row) = rows.iter().find(|r| r.guid == guid) {
+            root.folders
+                .push(build(row, name.to_string(), &children, &tags));
+        }
+    }
+    Ok(root)
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn read_firefox(_path: &Path) -> Result<Folder> {
+    anyhow::bail!("Firefox import requires the `sqlite` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten. <DL> inside a comment is ignored. -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="1792229412">Example &amp; Co</A>
+    <DT><H3 ADD_DATE="1700000000">Dev Tools</H3>
+    <DL><p>
+        <DT><A HREF="https://github.com/" TAGS="code,git">GitHub</A>
+        <DD>Where the code lives
+        <DT><a href='https://docs.rs'>Docs.rs</a>
+        <DT><A HREF="https://example.com/?a>b" ICON="data:image/svg+xml,<svg>x</svg>">Arrows</A>
+        <DT><H3>Rust</H3>
+        <DL><p>
+            <DT><A HREF="https://doc.rust-lang.org/book/">The Rust Programming Language</A>
+            <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+        </DL><p>
+    </DL><p>
+    <DT><H3>Empty</H3>
+    <DL><p>
+        <DT><A HREF="place:sort=8">Recent Tags</A>
+    </DL><p>
+</DL>
+"#;
+
+    fn bookmark(title: &str, url: &str) -> Bookmark {
+        Bookmark {
+            title: title.to_string(),
+            url: url.to_string(),
+            ..Bookmark::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_netscape() {
+        let root = parse_netscape(EXPORT).unwrap();
+        assert_eq!(root.name, "");
+        assert_eq!(root.len(), 5);
+        assert_eq!(root.bookmarks[0].title, "Example & Co");
+        assert_eq!(
+            root.bookmarks[0].added.unwrap().to_string(),
+            "2026-10-17T09:30:12Z"
+        );
+
+        let dev = &root.folders[0];
+        assert_eq!(dev.name, "Dev Tools");
+        assert_eq!(dev.bookmarks[0].tags, vec!["code", "git"]);
+        assert_eq!(
+            dev.bookmarks[0].description.as_deref(),
+            Some("Where the code lives")
+        );
+        assert_eq!(dev.bookmarks[1].url, "https://docs.rs");
+        assert_eq!(dev.bookmarks[1].description, None);
+        // A `>` inside a quoted attribute doesn't end the tag
+        assert_eq!(dev.bookmarks[2].url, "https://example.com/?a>b");
+        assert_eq!(dev.bookmarks[2].title, "Arrows");
+        assert_eq!(dev.folders[0].name, "Rust");
+        assert_eq!(dev.folders[0].bookmarks.len(), 1);
+
+        assert_eq!(root.folders[1].name, "Empty");
+        assert!(root.folders[1].is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_html() {
+        let err = parse_netscape("<html><body></body></html>").unwrap_err();
+        assert!(err.to_string().contains("not a Netscape bookmark file"));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode("a &amp; b &lt;c&gt; &#39;d&#x27; &bogus; &"),
+            "a & b <c> 'd' &bogus; &"
+        );
+    }
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(
+            slug("The Rust Programming Language"),
+            "the-rust-programming-language"
+        );
+        assert_eq!(
+            slug("  GitHub: Let's build from here "),
+            "github-let-s-build-from-here"
+        );
+        assert_eq!(slug("Überblick"), "überblick");
+        assert_eq!(slug("!!!"), "");
+        assert!(slug(&"word ".repeat(20)).len() <= MAX_NAME_LEN);
+        assert_eq!(
+            link_name(&bookmark("", "https://www.example.com/x")),
+            "example-com"
+        );
+    }
+
+    #[test]
+    fn test_import_folders_as_groups() {
+        let mut config = Config::default();
+        let report = import(
+            &mut config,
+            &parse_netscape(EXPORT).unwrap(),
+            OnConflict::Skip,
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.links,
+            vec![
+                "example-co",
+                "github",
+                "docs-rs",
+                "arrows",
+                "the-rust-programming-language"
+            ]
+        );
+        assert_eq!(report.groups, vec!["rust", "dev-tools"]);
+        assert_eq!(config.groups["rust"], vec!["the-rust-programming-language"]);
+        assert_eq!(
+            config.groups["dev-tools"],
+            vec!["github", "docs-rs", "arrows", "rust"]
+        );
+        assert_eq!(config.links["github"].tags, vec!["code", "git"]);
+        assert!(config.validate().is_empty());
+        assert_eq!(report.summary(), "imported 5 links and 2 groups");
+    }
+
+    #[test]
+    fn test_reimport_reuses_existing_links() {
+        let mut config = Config::default();
+        let root = parse_netscape(EXPORT).unwrap();
+        import(&mut config, &root, OnConflict::Rename).unwrap();
+        let report = import(&mut config, &root, OnConflict::Rename).unwrap();
+
+        assert!(report.links.is_empty());
+        assert!(report.groups.is_empty());
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.existing.len(), 5);
+        assert_eq!(config.links.len(), 5);
+        assert_eq!(
+            config.groups["dev-tools"],
+            vec!["github", "docs-rs", "arrows", "rust"]
+        );
+    }
+
+    fn conflicting(on_conflict: OnConflict) -> (Config, Report) {
+        let mut config: Config = toml::from_str(
+            "[links]\ngithub = \"https://github.com/me\"\n[aliases]\ndocs = \"github\"\n",
+        )
+        .unwrap();
+        let root = Folder {
+            bookmarks: vec![
+                bookmark("GitHub", "https://github.com"),
+                bookmark("Docs", "https://docs.rs"),
+            ],
+            ..Folder::default()
+        };
+        let report = import(&mut config, &root, on_conflict).unwrap();
+        (config, report)
+    }
+
+    #[test]
+    fn test_conflicts_skip() {
+        let (config, report) = conflicting(OnConflict::Skip);
+        assert!(report.links.is_empty());
+        assert_eq!(config.links["github"].url, "https://github.com/me");
+        assert_eq!(
+            report.conflicts[0].to_string(),
+            "skipped 'github': already a link"
+        );
+        assert_eq!(
+            report.conflicts[1].to_string(),
+            "skipped 'docs': already an alias"
+        );
+    }
+
+    #[test]
+    fn test_conflicts_overwrite() {
+        let (config, report) = conflicting(OnConflict::Overwrite);
+        assert_eq!(report.links, vec!["github", "docs"]);
+        assert_eq!(config.links["github"].url, "https://github.com");
+        assert_eq!(config.links["docs"].url, "https://docs.rs");
+        assert!(config.aliases.is_empty());
+        assert_eq!(report.conflicts[1].resolution, Resolution::Overwritten);
+    }
+
+    #[test]
+    fn test_conflicts_rename() {
+        let (config, report) = conflicting(OnConflict::Rename);
+        assert_eq!(report.links, vec!["github-2", "docs-2"]);
+        assert_eq!(config.links["github"].url, "https://github.com/me");
+        assert_eq!(config.links["github-2"].url, "https://github.com");
+        assert_eq!(
+            report.conflicts[0].to_string(),
+            "imported 'github' as 'github-2': 'github' is already a link"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_titles_in_one_import_are_renamed() {
+        let mut config = Config::default();
+        let root = Folder {
+            bookmarks: vec![
+                bookmark("Docs", "https://docs.rs"),
+                bookmark("Docs", "https://docs.python.org"),
+            ],
+            folders: vec![Folder {
+                name: "docs".to_string(),
+                bookmarks: vec![bookmark("Go", "https://go.dev/doc")],
+                ..Folder::default()
+            }],
+            ..Folder::default()
+        };
+        let report = import(&mut config, &root, OnConflict::Skip).unwrap();
+        assert_eq!(report.links, vec!["docs", "docs-2", "go"]);
+        assert_eq!(report.groups, vec!["docs-3"]);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_find_folder() {
+        let root = parse_netscape(EXPORT).unwrap();
+        assert_eq!(root.find("dev tools").unwrap().name, "Dev Tools");
+        assert_eq!(root.find("Rust").unwrap().bookmarks.len(), 1);
+        assert_eq!(root.find("Dev Tools/Rust").unwrap().name, "Rust");
+        assert!(root.find("Empty/Rust").is_none());
+        assert!(root.find("").is_none());
+    }
+
+    const CHROMIUM: &str = r#"{
+   "checksum": "0123456789abcdef",
+   "roots": {
+      "bookmark_bar": {
+         "children": [ {
+            "date_added": "13300000000000000",
+            "guid": "00000000-0000-4000-8000-000000000001",
+            "id": "5",
+            "name": "GitHub",
+            "type": "url",
+            "url": "https://github.com/"
+         }, {
+            "children": [ {
+               "date_added": "0",
+               "id": "7",
+               "name": "Docs.rs",
+               "type": "url",
+               "url": "https://docs.rs/"
+            }, {
+               "id": "8",
+               "name": "Bookmarklet",
+               "type": "url",
+               "url": "javascript:alert(1)"
+            } ],
+            "id": "6",
+            "name": "Rust",
+            "type": "folder"
+         } ],
+         "id": "1",
+         "name": "Bookmarks bar",
+         "type": "folder"
+      },
+      "other": {
+         "children": [ ],
+         "id": "2",
+         "name": "Other bookmarks",
+         "type": "folder"
+      },
+      "sync_transaction_version": "1"
+   },
+   "version": 1
+}"#;
+
+    #[test]
+    fn test_parse_chromium() {
+        let root = parse_chromium(CHROMIUM).unwrap();
+        assert_eq!(root.len(), 2);
+        let names: Vec<&str> = root.folders.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["Bookmarks bar", "Other bookmarks"]);
+
+        let bar = &root.folders[0];
+        assert_eq!(bar.bookmarks[0].url, "https://github.com/");
+        assert_eq!(
+            bar.bookmarks[0].added.unwrap().to_string(),
+            "2022-06-18T04:26:40Z"
+        );
+        assert_eq!(bar.folders[0].name, "Rust");
+        assert_eq!(bar.folders[0].bookmarks[0].title, "Docs.rs");
+        assert_eq!(bar.folders[0].bookmarks[0].added, None);
+
+        let err = parse_chromium("{}").unwrap_err();
+        assert!(err.to_string().contains("not a Chromium Bookmarks file"));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_read_firefox() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(FIREFOX_FILE);
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        // The columns read from a real profile, with Firefox's root guids
+        conn.execute_batch(
+            "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT);
+             CREATE TABLE moz_bookmarks (
+                 id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER,
+                 position INTEGER, title TEXT, dateAdded INTEGER, guid TEXT
+             );
+             INSERT INTO moz_places VALUES
+                 (1, 'https://github.com/'), (2, 'https://docs.rs/'), (3, 'place:sort=8');
+             INSERT INTO moz_bookmarks VALUES
+                 (1, 2, NULL, 0, 0, '', 0, 'root________'),
+                 (2, 2, NULL, 1, 0, 'menu', 0, 'menu________'),
+                 (3, 2, NULL, 1, 1, 'toolbar', 0, 'toolbar_____'),
+                 (4, 2, NULL, 1, 2, 'tags', 0, 'tags________'),
+                 (5, 2, NULL, 1, 3, 'unfiled', 0, 'unfiled_____'),
+                 (6, 1, 2, 3, 1, 'Docs.rs', 1792229412000000, 'bookmark0001'),
+                 (7, 2, NULL, 3, 0, 'Dev', 0, 'folder000001'),
+                 (8, 1, 1, 7, 0, 'GitHub', 0, 'bookmark0002'),
+                 (9, 3, NULL, 7, 1, NULL, 0, 'separator001'),
+                 (10, 2, NULL, 4, 0, 'code', 0, 'tag000000001'),
+                 (11, 1, 1, 10, 0, NULL, 0, 'tagged000001'),
+                 (12, 1, 3, 2, 0, 'Recent Tags', 0, 'query0000001');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let root = read_firefox(&path).unwrap();
+        let names: Vec<&str> = root.folders.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Bookmarks Menu", "Bookmarks Toolbar", "Other Bookmarks"]
+        );
+        assert_eq!(root.len(), 2);
+
+        let toolbar = &root.folders[1];
+        assert_eq!(toolbar.bookmarks[0].title, "Docs.rs");
+        assert_eq!(
+            toolbar.bookmarks[0].added.unwrap().to_string(),
+            "2026-10-17T09:30:12Z"
+        );
+        let dev = &toolbar.folders[0];
+        assert_eq!(dev.name, "Dev");
+        assert_eq!(dev.bookmarks[0].url, "https://github.com/");
+        assert_eq!(dev.bookmarks[0].tags, vec!["code"]);
+    }
+}
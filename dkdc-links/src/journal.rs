@@ -0,0 +1,576 @@
+//! Undo and redo for config changes. Every change saved through a
+//! [`JournaledStorage`] is recorded as the entries it touched, before and
+//! after, so it can be reverted or replayed later.
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use toml::value::Datetime;
+
+use crate::config::{now, Config, Link, Section};
+use crate::diagnostic::Diagnostic;
+use crate::storage::{sidecar, FileLock, Layered, Storage, Versioned};
+
+/// How many changes are kept for undo.
+const MAX_ENTRIES: usize = 100;
+
+/// A link, alias or group as it was before or after a change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Slot {
+    /// Position within its section, so undoing a delete puts it back in place.
+    index: usize,
+    value: toml::Value,
+}
+
+/// One entry of one section that a change added, removed or modified.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Change {
+    section: Section,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    before: Option<Slot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    after: Option<Slot>,
+}
+
+/// Everything one save changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    time: Datetime,
+    changes: Vec<Change>,
+}
+
+impl Entry {
+    /// What the change did, e.g. `delete link github, delete alias gh`.
+    fn summary(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        let mut renamed = Vec::new();
+        for (i, change) in self.changes.iter().enumerate() {
+            if renamed.contains(&i) {
+                continue;
+            }
+            let kind = change.section.kind();
+            let part = match (&change.before, &change.after) {
+                (Some(before), None) => {
+                    // A removal and an addition of the same value is a rename
+                    let rename = self.changes.iter().enumerate().position(|(j, c)| {
+                        !renamed.contains(&j)
+                            && c.section == change.section
+                            && c.before.is_none()
+                            && c.after.as_ref().map(|a| &a.value) == Some(&before.value)
+                    });
+                    match rename {
+                        Some(j) => {
+                            renamed.push(j);
+                            format!("rename {kind} {} -> {}", change.name, self.changes[j].name)
+                        }
+                        None => format!("delete {kind} {}", change.name),
+                    }
+                }
+                (None, _) => format!("add {kind} {}", change.name),
+                (Some(_), Some(_)) => format!("change {kind} {}", change.name),
+            };
+            parts.push(part);
+        }
+        parts.join(", ")
+    }
+}
+
+/// What changed from `before` to `after`, in the same words as an undo
+/// summary. Empty if no entry changed.
+pub(crate) fn describe(before: &Config, after: &Config) -> Result<String> {
+    let entry = Entry {
+        time: now(),
+        changes: diff(before, after)?,
+    };
+    Ok(entry.summary())
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Journal {
+    #[serde(default)]
+    undo: Vec<Entry>,
+    #[serde(default)]
+    redo: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Wrapper<V> {
+    value: V,
+}
+
+/// `value` as TOML. Goes through text so datetimes come back as
+/// [`toml::Value::Datetime`], the same as when the journal is read from disk.
+fn to_value<V: Serialize>(value: &V) -> Result<toml::Value> {
+    let text = toml::to_string(&Wrapper { value }).context("Failed to record change")?;
+    let wrapper: Wrapper<toml::Value> = toml::from_str(&text).context("Failed to record change")?;
+    Ok(wrapper.value)
+}
+
+/// The inverse of [`to_value`].
+fn from_value<V: DeserializeOwned>(value: &toml::Value) -> Result<V> {
+    let text = toml::to_string(&Wrapper { value }).context("Failed to restore entry")?;
+    let wrapper: Wrapper<V> = toml::from_str(&text).context("Failed to restore entry")?;
+    Ok(wrapper.value)
+}
+
+/// The entries that differ between `before` and `after`.
+fn diff(before: &Config, after: &Config) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    diff_section(Section::Links, &before.links, &after.links, &mut changes)?;
+    diff_section(
+        Section::Aliases,
+        &before.aliases,
+        &after.aliases,
+        &mut changes,
+    )?;
+    diff_section(Section::Groups, &before.groups, &after.groups, &mut changes)?;
+    Ok(changes)
+}
+
+fn diff_section<V: Serialize + PartialEq>(
+    section: Section,
+    before: &IndexMap<String, V>,
+    after: &IndexMap<String, V>,
+    changes: &mut Vec<Change>,
+) -> Result<()> {
+    let slot = |index, value: &V| -> Result<Slot> {
+        Ok(Slot {
+            index,
+            value: to_value(value)?,
+        })
+    };
+    for (i, (name, old)) in before.iter().enumerate() {
+        let new = match after.get_full(name) {
+            Some((_, _, new)) if new == old => continue,
+            Some((j, _, new)) => Some(slot(j, new)?),
+            None => None,
+        };
+        changes.push(Change {
+            section,
+            name: name.clone(),
+            before: Some(slot(i, old)?),
+            after: new,
+        });
+    }
+    for (j, (name, new)) in after.iter().enumerate() {
+        if !before.contains_key(name) {
+            changes.push(Change {
+                section,
+                name: name.clone(),
+                before: None,
+                after: Some(slot(j, new)?),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Move `config` from one side of `changes` to the other: from `before` to
+/// `after` when replaying, from `after` to `before` when reverting. Fails
+/// without touching `config` if any entry no longer matches the side it is
+/// moving from.
+fn apply(config: &mut Config, changes: &[Change], forward: bool) -> Result<()> {
+    for change in changes {
+        let (from, _) = sides(change, forward);
+        let current = get(config, change.section, &change.name)?;
+        if current.as_ref() != from.map(|slot| &slot.value) {
+            anyhow::bail!(
+                "'{}' in [{}] changed since; edit it by hand instead",
+                change.name,
+                change.section
+            );
+        }
+    }
+
+    for change in changes {
+        config.remove(change.section, &change.name);
+    }
+    let mut inserts: Vec<(&Change, &Slot)> = changes
+        .iter()
+        .filter_map(|change| sides(change, forward).1.map(|slot| (change, slot)))
+        .collect();
+    inserts.sort_by_key(|(_, slot)| slot.index);
+    for (change, slot) in inserts {
+        insert(config, change.section, &change.name, slot)?;
+    }
+    Ok(())
+}
+
+/// The slot a change moves from and the one it moves to.
+fn sides(change: &Change, forward: bool) -> (Option<&Slot>, Option<&Slot>) {
+    if forward {
+        (change.before.as_ref(), change.after.as_ref())
+    } else {
+        (change.after.as_ref(), change.before.as_ref())
+    }
+}
+
+fn get(config: &Config, section: Section, name: &str) -> Result<Option<toml::Value>> {
+    fn value<V: Serialize>(map: &IndexMap<String, V>, name: &str) -> Result<Option<toml::Value>> {
+        map.get(name).map(to_value).transpose()
+    }
+    match section {
+        Section::Links => value(&config.links, name),
+        Section::Aliases => value(&config.aliases, name),
+        Section::Groups => value(&config.groups, name),
+    }
+}
+
+fn insert(config: &mut Config, section: Section, name: &str, slot: &Slot) -> Result<()> {
+    fn put<V: DeserializeOwned>(
+        map: &mut IndexMap<String, V>,
+        name: &str,
+        slot: &Slot,
+    ) -> Result<()> {
+        let value = from_value(&slot.value)?;
+        map.shift_insert(slot.index.min(map.len()), name.to_string(), value);
+        Ok(())
+    }
+    match section {
+        Section::Links => put::<Link>(&mut config.links, name, slot),
+        Section::Aliases => put(&mut config.aliases, name, slot),
+        Section::Groups => put(&mut config.groups, name, slot),
+    }
+}
+
+/// Wraps another backend and records every change it saves, for
+/// [`JournaledStorage::undo`] and [`JournaledStorage::redo`]. The journal is kept next to the
+/// stored data as `<file>.undo`, or in memory for backends without a path.
+pub struct JournaledStorage {
+    inner: Box<dyn Storage>,
+    path: Option<PathBuf>,
+    /// The journal itself when there is no file; otherwise a cache that also
+    /// serializes access from this process.
+    journal: Mutex<Journal>,
+}
+
+impl JournaledStorage {
+    pub fn new(inner: Box<dyn Storage>) -> Self {
+        let path = inner.path().map(|p| sidecar(p, ".undo"));
+        Self {
+            inner,
+            path,
+            journal: Mutex::new(Journal::default()),
+        }
+    }
+
+    /// Run `f` on the journal, reading it from disk first and writing it back
+    /// afterwards, all under the backend's lock so the journal and the data it
+    /// describes change together.
+    fn with_journal<T>(&self, f: impl FnOnce(&mut Journal) -> Result<T>) -> Result<T> {
+        let _lock = self.inner.lock()?;
+        let mut journal = self.journal.lock().unwrap();
+        if let Some(path) = &self.path {
+            *journal = read_journal(path)?;
+        }
+        let result = f(&mut journal)?;
+        if let Some(path) = &self.path {
+            write_journal(path, &journal)?;
+        }
+        Ok(result)
+    }
+
+    /// Push an undo entry for `changes`, returning its summary, or `None`
+    /// if there are no changes to record.
+    fn record(&self, changes: Vec<Change>) -> Result<Option<String>> {
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        self.with_journal(|journal| {
+            let entry = Entry {
+                time: now(),
+                changes,
+            };
+            let summary = entry.summary();
+            journal.undo.push(entry);
+            let excess = journal.undo.len().saturating_sub(MAX_ENTRIES);
+            journal.undo.drain(..excess);
+            journal.redo.clear();
+            Ok(Some(summary))
+        })
+    }
+
+    /// Like [`Storage::update`], but return the summary of the change it
+    /// recorded, or `None` if `f` left the config as it was.
+    pub fn change(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<Option<String>> {
+        let _lock = self.inner.lock()?;
+        let mut changes = Vec::new();
+        self.inner.update(&mut |config| {
+            let before = config.clone();
+            f(config)?;
+            changes = diff(&before, config)?;
+            Ok(())
+        })?;
+        self.record(changes)
+    }
+
+    /// Revert the most recent change, returning what it did, or `None` if
+    /// there is nothing to undo.
+    pub fn undo(&self) -> Result<Option<String>> {
+        self.step(false)
+    }
+
+    /// Replay the most recently undone change.
+    pub fn redo(&self) -> Result<Option<String>> {
+        self.step(true)
+    }
+
+    /// What `undo` would revert, if anything.
+    pub fn last_change(&self) -> Result<Option<String>> {
+        self.with_journal(|journal| Ok(journal.undo.last().map(Entry::summary)))
+    }
+
+    /// Revert the latest undo entry or replay the latest redo entry.
+    fn step(&self, forward: bool) -> Result<Option<String>> {
+        self.with_journal(|journal| {
+            let (from, to) = if forward {
+                (&mut journal.redo, &mut journal.undo)
+            } else {
+                (&mut journal.undo, &mut journal.redo)
+            };
+            let Some(entry) = from.last() else {
+                return Ok(None);
+            };
+            let verb = if forward { "redo" } else { "undo" };
+            let summary = entry.summary();
+            let change = &mut |config: &mut Config| apply(config, &entry.changes, forward);
+            match self.inner.versioned() {
+                Some(versioned) => versioned.update_as(&format!("{verb}: {summary}"), change),
+                None => self.inner.update(change),
+            }
+            .with_context(|| format!("Failed to {verb}"))?;
+            let entry = from.pop().expect("checked above");
+            to.push(entry);
+            Ok(Some(summary))
+        })
+    }
+}
+
+fn read_journal(path: &Path) -> Result<Journal> {
+    match fs::read_to_string(path) {
+        Ok(contents) => toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse undo history {}", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Journal::default()),
+        Err(e) => Err(e).context("Failed to read undo history"),
+    }
+}
+
+fn write_journal(path: &Path, journal: &Journal) -> Result<()> {
+    let contents = toml::to_string(journal).context("Failed to serialize undo history")?;
+    let tmp_path = sidecar(path, ".tmp");
+    let mut tmp = File::create(&tmp_path).context("Failed to write undo history")?;
+    tmp.write_all(contents.as_bytes())
+        .context("Failed to write undo history")?;
+    fs::rename(&tmp_path, path).context("Failed to replace undo history")
+}
+
+impl Storage for JournaledStorage {
+    fn load(&self) -> Result<Config> {
+        self.inner.load()
+    }
+
+    fn save(&self, config: &Config) -> Result<()> {
+        let _lock = self.inner.lock()?;
+        let before = self.inner.stored().unwrap_or_default();
+        self.inner.save(config)?;
+        self.record(diff(&before, config)?).map(drop)
+    }
+
+    fn update(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        self.change(f).map(drop)
+    }
+
+    fn init(&self) -> Result<()> {
+        self.inner.init()
+    }
+
+    fn backend_name(&self) -> &str {
+        self.inner.backend_name()
+    }
+
+    fn path(&self) -> Option<&Path> {
+        self.inner.path()
+    }
+
+    fn stored(&self) -> Result<Config> {
+        self.inner.stored()
+    }
+
+    fn lock(&self) -> Result<Option<FileLock>> {
+        self.inner.lock()
+    }
+
+    fn diagnostics(&self) -> Result<Vec<Diagnostic>> {
+        self.inner.diagnostics()
+    }
+
+    fn get_link(&self, name: &str) -> Result<Option<Link>> {
+        self.inner.get_link(name)
+    }
+
+    fn get_alias(&self, name: &str) -> Result<Option<String>> {
+        self.inner.get_alias(name)
+    }
+
+    fn get_group(&self, name: &str) -> Result<Option<Vec<String>>> {
+        self.inner.get_group(name)
+    }
+
+    fn versioned(&self) -> Option<&dyn Versioned> {
+        self.inner.versioned()
+    }
+
+    fn layered(&self) -> Option<&dyn Layered> {
+        self.inner.layered()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DeleteMode, DEFAULT_CONFIG};
+    use crate::storage::ConflictError;
+    use crate::toml_storage::TomlStorage;
+    use tempfile::TempDir;
+
+    fn storage() -> (TempDir, JournaledStorage) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, DEFAULT_CONFIG).unwrap();
+        let storage = JournaledStorage::new(Box::new(TomlStorage::new(path)));
+        (dir, storage)
+    }
+
+    #[test]
+    fn test_undo_and_redo_a_delete() {
+        let (dir, storage) = storage();
+        let original = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+
+        storage.delete("link1", DeleteMode::Cascade).unwrap();
+        assert_eq!(
+            storage.last_change().unwrap().as_deref(),
+            Some("delete link link1, delete alias alias1, delete alias a1, change group dev")
+        );
+
+        assert_eq!(
+            storage.undo().unwrap().as_deref(),
+            Some("delete link link1, delete alias alias1, delete alias a1, change group dev")
+        );
+        let restored = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+        assert_eq!(restored, original);
+        assert_eq!(storage.undo().unwrap(), None);
+
+        storage.redo().unwrap();
+        let config = storage.load().unwrap();
+        assert!(!config.links.contains_key("link1"));
+        assert_eq!(config.groups["dev"], vec!["alias2"]);
+        assert_eq!(storage.redo().unwrap(), None);
+    }
+
+    #[test]
+    fn test_rename_summary_and_position() {
+        let (_dir, storage) = storage();
+        storage.rename_link("link1", "crates").unwrap();
+        assert_eq!(
+            storage.undo().unwrap().as_deref(),
+            Some("rename link link1 -> crates, change alias alias1, change alias a1")
+        );
+        let config = storage.load().unwrap();
+        let links: Vec<_> = config.links.keys().map(String::as_str).collect();
+        assert_eq!(links, vec!["link1", "link2"]);
+        assert_eq!(config.aliases["alias1"], "link1");
+    }
+
+    #[test]
+    fn test_new_change_clears_redo() {
+        let (_dir, storage) = storage();
+        storage.put_link("a", "https://a.example.com").unwrap();
+        storage.undo().unwrap();
+        storage.put_link("b", "https://b.example.com").unwrap();
+        assert_eq!(storage.redo().unwrap(), None);
+        assert_eq!(storage.undo().unwrap().as_deref(), Some("add link b"));
+    }
+
+    #[test]
+    fn test_change_returns_what_it_recorded() {
+        let (_dir, storage) = storage();
+        let change = storage.change(&mut |config| config.add_link("a", "https://a.example.com"));
+        assert_eq!(change.unwrap().as_deref(), Some("add link a"));
+
+        let unchanged = storage.change(&mut |config| config.add_link("a", "https://a.example.com"));
+        assert_eq!(unchanged.unwrap(), None);
+        assert_eq!(
+            storage.last_change().unwrap().as_deref(),
+            Some("add link a")
+        );
+    }
+
+    #[test]
+    fn test_history_survives_reopening() {
+        let (dir, storage) = storage();
+        storage.put_alias("l1", "link1").unwrap();
+        drop(storage);
+
+        let path = dir.path().join("config.toml");
+        let storage = JournaledStorage::new(Box::new(TomlStorage::new(path)));
+        assert_eq!(storage.undo().unwrap().as_deref(), Some("add alias l1"));
+        assert!(!storage.load().unwrap().aliases.contains_key("l1"));
+    }
+
+    #[test]
+    fn test_undo_refuses_when_edited_since() {
+        let (dir, storage) = storage();
+        storage.put_link("link1", "https://example.com").unwrap();
+
+        let path = dir.path().join("config.toml");
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("https://example.com", "https://example.org");
+        fs::write(&path, edited).unwrap();
+
+        let err = storage.undo().unwrap_err();
+        assert!(format!("{err:#}").contains("'link1' in [links] changed since"));
+        assert_eq!(
+            storage.last_change().unwrap().as_deref(),
+            Some("change link link1")
+        );
+    }
+
+    #[test]
+    fn test_save_records_changes() {
+        let (_dir, storage) = storage();
+        let mut config = storage.load().unwrap();
+        config.groups.shift_remove("dev");
+        storage.save(&config).unwrap();
+        assert_eq!(storage.undo().unwrap().as_deref(), Some("delete group dev"));
+        assert_eq!(
+            storage.load().unwrap().groups["dev"],
+            vec!["alias1", "alias2"]
+        );
+    }
+
+    #[test]
+    fn test_save_refuses_stale_config() {
+        let (_dir, storage) = storage();
+        let mut stale = storage.load().unwrap();
+        storage.put_link("new", "https://new.example.com").unwrap();
+        stale.groups.shift_remove("dev");
+        let err = storage.save(&stale).unwrap_err();
+        assert!(err.downcast_ref::<ConflictError>().is_some());
+        assert_eq!(
+            storage.last_change().unwrap().as_deref(),
+            Some("add link new")
+        );
+
+        let mut config = storage.load().unwrap();
+        config.groups.shift_remove("dev");
+        storage.save(&config).unwrap();
+        assert_eq!(storage.undo().unwrap().as_deref(), Some("delete group dev"));
+        assert!(storage.load().unwrap().links.contains_key("new"));
+    }
+}
@@ -0,0 +1,675 @@
+//! Several TOML files merged into one config, e.g. a system-wide file, a
+//! team file checked into a repo, and personal overrides on top.
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::slice;
+
+use crate::config::{Config, Section};
+use crate::diagnostic::{self, Diagnostic};
+use crate::storage::{ConflictError, FileLock, Layered, Storage};
+use crate::toml_storage::TomlStorage;
+
+/// System-wide layer, read by everyone on the machine.
+pub const SYSTEM_PATH: &str = "/etc/dkdc/links.toml";
+
+/// Environment variable naming the team layer's file.
+pub const TEAM_ENV: &str = "DKDC_LINKS_TEAM";
+
+/// Project layer, found by walking up from the working directory.
+pub const PROJECT_FILENAME: &str = ".dkdc-links.toml";
+
+/// One file of a layered config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    /// Short name shown to users, e.g. `team`.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Layer {
+    pub fn new(name: &str, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.into(),
+        }
+    }
+}
+
+/// Where one entry of the merged config came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub section: Section,
+    pub name: String,
+    /// The layer whose definition wins.
+    pub layer: Layer,
+    /// Lower layers that also define the name, highest first.
+    pub overrides: Vec<String>,
+}
+
+/// Merges its layers in order, later ones overriding earlier ones entry by
+/// entry. Changes are written to one layer, as overrides of whatever the
+/// other layers define.
+pub struct LayeredStorage {
+    /// Lowest precedence first.
+    layers: Vec<(Layer, TomlStorage)>,
+    /// Index of the layer changes are written to.
+    target: usize,
+}
+
+impl LayeredStorage {
+    /// Layers in precedence order, lowest first. Changes go to the layer
+    /// named `target`, or by default to the topmost layer whose file can be
+    /// written or created. A missing file is created on the first change.
+    pub fn new(layers: Vec<Layer>, target: Option<&str>) -> Result<Self> {
+        let target = match target {
+            Some(target) => {
+                let Some(index) = layers.iter().position(|layer| layer.name == target) else {
+                    let names: Vec<&str> = layers.iter().map(|layer| layer.name.as_str()).collect();
+                    anyhow::bail!(
+                        "no {target} layer to write to; the layers are {}",
+                        names.join(", ")
+                    );
+                };
+                let path = &layers[index].path;
+                if !can_write(path) {
+                    anyhow::bail!("the {target} layer {} is not writable", path.display());
+                }
+                index
+            }
+            None => layers
+                .iter()
+                .rposition(|layer| can_write(&layer.path))
+                .context("none of the config layers can be written to")?,
+        };
+        let layers = layers
+            .into_iter()
+            .map(|layer| {
+                let storage = TomlStorage::new(layer.path.clone());
+                (layer, storage)
+            })
+            .collect();
+        Ok(Self { layers, target })
+    }
+
+    /// The standard layers: [`SYSTEM_PATH`], the file named by [`TEAM_ENV`]
+    /// if set, the user's config, and the nearest [`PROJECT_FILENAME`].
+    /// Changes go to `target` as in [`LayeredStorage::new`].
+    pub fn discover(target: Option<&str>) -> Result<Self> {
+        let mut layers = vec![Layer::new("system", SYSTEM_PATH)];
+        if let Some(team) = env::var_os(TEAM_ENV).filter(|v| !v.is_empty()) {
+            layers.push(Layer::new("team", team));
+        }
+        layers.push(Layer::new("user", TomlStorage::default_path()?));
+        let cwd = env::current_dir().context("Failed to get current directory")?;
+        if let Some(project) = find_project_file(&cwd) {
+            layers.push(Layer::new("project", project));
+        }
+        Self::new(layers, target)
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = &Layer> {
+        self.layers.iter().map(|(layer, _)| layer)
+    }
+
+    /// The layer changes are written to.
+    pub fn target(&self) -> &Layer {
+        &self.layers[self.target].0
+    }
+
+    /// Every layer's config, with missing files as empty configs.
+    fn read_layers(&self) -> Result<Vec<Config>> {
+        self.layers
+            .iter()
+            .map(|(layer, storage)| {
+                storage
+                    .read_config()
+                    .with_context(|| format!("Failed to read {} layer", layer.name))
+                    .map(Option::unwrap_or_default)
+            })
+            .collect()
+    }
+
+    /// The contents the target layer needs so that merging gives `desired`.
+    /// Fails if that would mean changing or removing an entry another layer
+    /// defines with higher precedence, or removing one a lower layer defines.
+    fn write_through(&self, configs: &[Config], desired: &Config) -> Result<Config> {
+        let current = merge(configs);
+        let mut target = configs[self.target].clone();
+
+        for (section, name) in current.entries() {
+            if !desired.has(section, name) {
+                target.remove(section, name);
+            }
+        }
+        for (name, link) in &desired.links {
+            if current.links.get(name) != Some(link) {
+                remove_everywhere(&mut target, name);
+                target.links.insert(name.clone(), link.clone());
+            }
+        }
+        for (name, alias) in &desired.aliases {
+            if current.aliases.get(name) != Some(alias) {
+                remove_everywhere(&mut target, name);
+                target.aliases.insert(name.clone(), alias.clone());
+            }
+        }
+        for (name, entries) in &desired.groups {
+            if current.groups.get(name) != Some(entries) {
+                remove_everywhere(&mut target, name);
+                target.groups.insert(name.clone(), entries.clone());
+            }
+        }
+
+        let mut result = configs.to_vec();
+        result[self.target] = target;
+        let merged = merge(&result);
+        let stuck = merged
+            .entries()
+            .chain(desired.entries())
+            .find(|(section, name)| get(&merged, *section, name) != get(desired, *section, name));
+        if let Some((_, name)) = stuck {
+            let (layer, _) = self
+                .layers
+                .iter()
+                .enumerate()
+                .rev()
+                .find(|(i, _)| *i != self.target && configs[*i].contains(name))
+                .map(|(_, layer)| layer)
+                .expect("only other layers can block a change");
+            anyhow::bail!(
+                "'{name}' is defined in the {} layer ({}), which can't be changed from the {} layer",
+                layer.name,
+                layer.path.display(),
+                self.target().name
+            );
+        }
+        Ok(result.swap_remove(self.target))
+    }
+
+    /// `diagnostic` rendered against the file of the layer its entry comes
+    /// from, with a source span where one can be found.
+    fn render(&self, configs: &[Config], mut diagnostic: Diagnostic) -> String {
+        let layer = Section::ALL
+            .into_iter()
+            .find(|section| section.as_str() == diagnostic.section)
+            .and_then(|section| {
+                self.layers
+                    .iter()
+                    .zip(configs)
+                    .rev()
+                    .find(|(_, config)| config.has(section, &diagnostic.key))
+            })
+            .map(|((layer, _), _)| layer);
+        let Some(layer) = layer else {
+            return diagnostic.render(None, None);
+        };
+        let source = fs::read_to_string(&layer.path).ok();
+        if let Some(source) = &source {
+            diagnostic::locate(slice::from_mut(&mut diagnostic), source);
+        }
+        diagnostic.render(Some(&layer.path), source.as_deref())
+    }
+
+    /// Create the target layer's file if needed, empty, so it can be updated.
+    fn create_target(&self) -> Result<()> {
+        let path = &self.target().path;
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).context("Failed to create config directory")?;
+            }
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn update_merged(
+        &self,
+        f: &mut dyn FnMut(&Config, &mut Config) -> Result<()>,
+    ) -> Result<Config> {
+        self.create_target()?;
+        let mut merged = Config::default();
+        self.layers[self.target].1.update(&mut |target| {
+            let mut configs = self.read_layers()?;
+            configs[self.target] = target.clone();
+            let current = merge(&configs);
+            let mut desired = current.clone();
+            f(&current, &mut desired)?;
+            *target = self.write_through(&configs, &desired)?;
+            merged = desired;
+            Ok(())
+        })?;
+        Ok(merged)
+    }
+}
+
+/// Whether the current user can write to `path`, or create it if missing.
+/// A missing file counts as creatable if the nearest existing directory
+/// above it accepts new files, which is checked with a throwaway file.
+fn can_write(path: &Path) -> bool {
+    if path.exists() {
+        return OpenOptions::new().append(true).open(path).is_ok();
+    }
+    let Some(dir) = path.ancestors().skip(1).find(|dir| dir.exists()) else {
+        return false;
+    };
+    let probe = dir.join(format!(".dkdc-links-{}.probe", std::process::id()));
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => fs::remove_file(&probe).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// The nearest [`PROJECT_FILENAME`] in `dir` or one of its ancestors.
+pub fn find_project_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_FILENAME))
+        .find(|path| path.is_file())
+}
+
+/// Merge `configs`, lowest precedence first. A name defined in a later
+/// config replaces it in every section, so a layer can turn a link into an
+/// alias; a replaced entry keeps its position.
+fn merge(configs: &[Config]) -> Config {
+    let mut merged = Config::default();
+    for config in configs {
+        for name in config.names() {
+            for section in Section::ALL {
+                if !config.has(section, name) {
+                    merged.remove(section, name);
+                }
+            }
+        }
+        merged.links.extend(config.links.clone());
+        merged.aliases.extend(config.aliases.clone());
+        merged.groups.extend(config.groups.clone());
+    }
+    merged
+}
+
+/// The entry as TOML, for comparing entries across sections.
+fn get(config: &Config, section: Section, name: &str) -> Option<toml::Value> {
+    fn value<V: serde::Serialize>(map: &IndexMap<String, V>, name: &str) -> Option<toml::Value> {
+        map.get(name).and_then(|v| toml::Value::try_from(v).ok())
+    }
+    match section {
+        Section::Links => value(&config.links, name),
+        Section::Aliases => value(&config.aliases, name),
+        Section::Groups => value(&config.groups, name),
+    }
+}
+
+fn remove_everywhere(config: &mut Config, name: &str) {
+    for section in Section::ALL {
+        config.remove(section, name);
+    }
+}
+
+impl Layered for LayeredStorage {
+    /// In the merged config's order.
+    fn origins(&self) -> Result<Vec<Origin>> {
+        let configs = self.read_layers()?;
+        let merged = merge(&configs);
+        let mut origins = Vec::new();
+        for (section, name) in merged.entries() {
+            let defined: Vec<usize> = configs
+                .iter()
+                .enumerate()
+                .filter(|(_, config)| config.has(section, name))
+                .map(|(i, _)| i)
+                .rev()
+                .collect();
+            let (winner, rest) = defined.split_first().expect("merged from a layer");
+            origins.push(Origin {
+                section,
+                name: name.to_string(),
+                layer: self.layers[*winner].0.clone(),
+                overrides: rest
+                    .iter()
+                    .map(|i| self.layers[*i].0.name.clone())
+                    .collect(),
+            });
+        }
+        Ok(origins)
+    }
+}
+
+impl Storage for LayeredStorage {
+    fn load(&self) -> Result<Config> {
+        let configs = self.read_layers()?;
+        let config = merge(&configs).loaded();
+        for diagnostic in config.validate() {
+            eprintln!("{}", self.render(&configs, diagnostic));
+        }
+        Ok(config)
+    }
+
+    /// Fails with [`ConflictError`] if any layer changed since `config` was
+    /// loaded.
+    fn save(&self, config: &Config) -> Result<()> {
+        self.update_merged(&mut |current, desired| {
+            if config.is_stale(Some(current)) {
+                return Err(ConflictError {
+                    path: self.target().path.clone(),
+                }
+                .into());
+            }
+            *desired = config.clone();
+            Ok(())
+        })
+        .map(drop)
+    }
+
+    fn update(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        self.update_merged(&mut |_, desired| f(desired)).map(drop)
+    }
+
+    /// Create the user's config with the defaults if no layer exists yet.
+    fn init(&self) -> Result<()> {
+        if self.layers().any(|layer| layer.path.exists()) {
+            return Ok(());
+        }
+        self.layers[self.target].1.init()
+    }
+
+    fn backend_name(&self) -> &str {
+        "layered"
+    }
+
+    /// The layer changes are written to.
+    fn path(&self) -> Option<&Path> {
+        Some(&self.target().path)
+    }
+
+    fn stored(&self) -> Result<Config> {
+        Ok(merge(&self.read_layers()?))
+    }
+
+    /// Locks the target layer, creating it first so there is a directory to
+    /// hold the lock file.
+    fn lock(&self) -> Result<Option<FileLock>> {
+        self.create_target()?;
+        self.layers[self.target].1.lock()
+    }
+
+    fn layered(&self) -> Option<&dyn Layered> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::JournaledStorage;
+    use tempfile::TempDir;
+
+    const TEAM: &str = r#"
+[links]
+github = "https://github.com"
+docs = "https://docs.example.com"
+wiki = "https://wiki.example.com"
+
+[aliases]
+gh = "github"
+"#;
+
+    const USER: &str = r#"
+[links]
+docs = "https://my-docs.example.com"
+rust = "https://rust-lang.org"
+"#;
+
+    /// A read-only team layer with a writable user layer on top.
+    fn storage(dir: &TempDir) -> LayeredStorage {
+        let team = dir.path().join("team.toml");
+        let user = dir.path().join("user.toml");
+        fs::write(&team, TEAM).unwrap();
+        fs::write(&user, USER).unwrap();
+        let mut permissions = fs::metadata(&team).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&team, permissions).unwrap();
+        LayeredStorage::new(
+            vec![Layer::new("team", team), Layer::new("user", user)],
+            Some("user"),
+        )
+        .unwrap()
+    }
+
+    fn user_file(dir: &TempDir) -> Config {
+        toml::from_str(&fs::read_to_string(dir.path().join("user.toml")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_merge_overrides_by_name() {
+        let dir = TempDir::new().unwrap();
+        let config = storage(&dir).load().unwrap();
+        assert_eq!(
+            config.links.keys().collect::<Vec<_>>(),
+            vec!["github", "docs", "wiki", "rust"]
+        );
+        assert_eq!(config.links["docs"].url, "https://my-docs.example.com");
+        assert_eq!(config.aliases["gh"], "github");
+    }
+
+    #[test]
+    fn test_higher_layer_can_change_section() {
+        let low: Config = toml::from_str("[links]\ngh = \"https://github.com\"\n").unwrap();
+        let high: Config = toml::from_str("[aliases]\ngh = \"github\"\n").unwrap();
+        let merged = merge(&[low, high]);
+        assert!(merged.links.is_empty());
+        assert_eq!(merged.aliases["gh"], "github");
+    }
+
+    #[test]
+    fn test_origins() {
+        let dir = TempDir::new().unwrap();
+        let origins = storage(&dir).origins().unwrap();
+        let found: Vec<_> = origins
+            .iter()
+            .map(|o| (o.name.as_str(), o.layer.name.as_str(), o.overrides.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("github", "team", vec![]),
+                ("docs", "user", vec!["team".to_string()]),
+                ("wiki", "team", vec![]),
+                ("rust", "user", vec![]),
+                ("gh", "team", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_writes_go_to_the_user_layer() {
+        let dir = TempDir::new().unwrap();
+        let storage = storage(&dir);
+        assert_eq!(storage.target().name, "user");
+
+        storage.put_link("crates", "https://crates.io").unwrap();
+        storage.put_alias("w", "wiki").unwrap();
+        // Overriding a team link copies it into the user layer
+        storage
+            .update(&mut |config| {
+                config.links["github"].description = Some("code".to_string());
+                Ok(())
+            })
+            .unwrap();
+
+        let user = user_file(&dir);
+        assert_eq!(
+            user.links.keys().collect::<Vec<_>>(),
+            vec!["docs", "rust", "crates", "github"]
+        );
+        assert_eq!(user.aliases["w"], "wiki");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("team.toml")).unwrap(),
+            TEAM
+        );
+    }
+
+    #[test]
+    fn test_removing_an_override_reveals_the_lower_entry() {
+        let dir = TempDir::new().unwrap();
+        let storage = storage(&dir);
+        let err = storage.delete_link("docs").unwrap_err().to_string();
+        assert!(err.contains("'docs' is defined in the team layer"), "{err}");
+
+        storage.delete_link("rust").unwrap();
+        assert!(!storage.load().unwrap().links.contains_key("rust"));
+    }
+
+    #[test]
+    fn test_lower_layer_entries_cannot_be_removed() {
+        let dir = TempDir::new().unwrap();
+        let storage = storage(&dir);
+        let err = storage.rename_link("wiki", "w").unwrap_err().to_string();
+        assert!(err.contains("'wiki' is defined in the team layer"), "{err}");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("user.toml")).unwrap(),
+            USER
+        );
+    }
+
+    #[test]
+    fn test_save_detects_changes_to_other_layers() {
+        let dir = TempDir::new().unwrap();
+        let team = dir.path().join("team.toml");
+        let user = dir.path().join("user.toml");
+        fs::write(&team, TEAM).unwrap();
+        fs::write(&user, USER).unwrap();
+        let storage = LayeredStorage::new(
+            vec![Layer::new("team", &team), Layer::new("user", user)],
+            Some("user"),
+        )
+        .unwrap();
+        assert_eq!(storage.target().name, "user");
+
+        let mut config = storage.load().unwrap();
+        fs::write(&team, format!("{TEAM}\n[groups]\ndev = [\"gh\"]\n")).unwrap();
+        config.add_link("crates", "https://crates.io").unwrap();
+        let err = storage.save(&config).unwrap_err();
+        assert!(err.downcast_ref::<ConflictError>().is_some());
+    }
+
+    #[test]
+    fn test_missing_user_layer_is_created_under_the_journal() {
+        let dir = TempDir::new().unwrap();
+        let team = dir.path().join("team.toml");
+        fs::write(&team, TEAM).unwrap();
+        let user = dir.path().join("nested/user.toml");
+        let storage = JournaledStorage::new(Box::new(
+            LayeredStorage::new(
+                vec![Layer::new("team", team), Layer::new("user", &user)],
+                None,
+            )
+            .unwrap(),
+        ));
+        storage.put_alias("g", "github").unwrap();
+        assert!(user.exists());
+        assert_eq!(storage.undo().unwrap().as_deref(), Some("add alias g"));
+    }
+
+    #[test]
+    fn test_missing_user_layer_is_created_on_write() {
+        let dir = TempDir::new().unwrap();
+        let team = dir.path().join("team.toml");
+        fs::write(&team, TEAM).unwrap();
+        let user = dir.path().join("nested/user.toml");
+        let storage = LayeredStorage::new(
+            vec![Layer::new("team", team), Layer::new("user", &user)],
+            Some("user"),
+        )
+        .unwrap();
+        storage.init().unwrap();
+        assert!(!user.exists());
+
+        storage.put_alias("g", "github").unwrap();
+        assert_eq!(
+            fs::read_to_string(&user).unwrap(),
+            "[aliases]\ng = \"github\"\n"
+        );
+    }
+
+    #[test]
+    fn test_changes_go_to_the_topmost_writable_layer() {
+        let dir = TempDir::new().unwrap();
+        let user = dir.path().join("user.toml");
+        let project = dir.path().join(PROJECT_FILENAME);
+        fs::write(&user, USER).unwrap();
+        fs::write(&project, "").unwrap();
+        let layers = vec![Layer::new("user", &user), Layer::new("project", &project)];
+
+        let storage = LayeredStorage::new(layers.clone(), None).unwrap();
+        assert_eq!(storage.target().name, "project");
+        storage.put_alias("c", "rust").unwrap();
+        assert_eq!(
+            fs::read_to_string(&project).unwrap(),
+            "[aliases]\nc = \"rust\"\n"
+        );
+
+        let storage = LayeredStorage::new(layers.clone(), Some("user")).unwrap();
+        storage.put_link("crates", "https://crates.io").unwrap();
+        assert!(user_file(&dir).links.contains_key("crates"));
+
+        // A layer whose file can't be created is passed over
+        let blocked = dir.path().join("not-a-dir");
+        fs::write(&blocked, "").unwrap();
+        let layers = vec![
+            Layer::new("user", &user),
+            Layer::new("project", blocked.join(PROJECT_FILENAME)),
+        ];
+        let storage = LayeredStorage::new(layers.clone(), None).unwrap();
+        assert_eq!(storage.target().name, "user");
+        let err = LayeredStorage::new(layers.clone(), Some("project"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.starts_with("the project layer"), "{err}");
+
+        let err = LayeredStorage::new(layers, Some("team"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            err,
+            "no team layer to write to; the layers are user, project"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_point_into_the_defining_layer() {
+        let dir = TempDir::new().unwrap();
+        let storage = storage(&dir);
+        let user = dir.path().join("user.toml");
+        fs::write(&user, format!("{USER}\n[aliases]\nr = \"rst\"\n")).unwrap();
+
+        let configs = storage.read_layers().unwrap();
+        let diagnostic = merge(&configs).validate().remove(0);
+        let rendered = storage.render(&configs, diagnostic);
+        let location = format!("--> {}:7:5", user.display());
+        assert!(rendered.contains(&location), "{rendered}");
+        assert!(rendered.contains("did you mean 'rust'?"), "{rendered}");
+    }
+
+    #[test]
+    fn test_find_project_file_walks_up() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_file(&nested), None);
+
+        fs::write(dir.path().join(PROJECT_FILENAME), "").unwrap();
+        assert_eq!(
+            find_project_file(&nested),
+            Some(dir.path().join(PROJECT_FILENAME))
+        );
+    }
+}
@@ -1,16 +1,33 @@
+pub mod backup;
 pub mod cli;
 pub mod config;
+pub mod diagnostic;
+pub mod doctor;
+pub mod export;
+pub mod git_storage;
+pub mod import;
+pub mod journal;
+pub mod layered_storage;
 pub mod open;
 pub mod storage;
 pub mod strings;
+pub mod template;
 pub mod toml_storage;
 
+#[cfg(feature = "sqlite")]
+pub mod sqlite_storage;
+
 #[cfg(feature = "app")]
 pub mod app;
 #[cfg(feature = "webapp")]
 pub mod webapp;
 
 pub use cli::run;
-pub use config::Config;
+pub use config::{Config, DeleteMode, Dependent, Link};
+pub use diagnostic::{Diagnostic, Severity};
+pub use git_storage::GitStorage;
+pub use layered_storage::LayeredStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite_storage::SqliteStorage;
 pub use storage::Storage;
 pub use toml_storage::TomlStorage;
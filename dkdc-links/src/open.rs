@@ -1,13 +1,20 @@
 use anyhow::{Context, Result};
 
 use crate::config::Config;
+use crate::template;
 
+/// The URL behind a link or alias, with any template placeholders left in
+/// place; see [`resolve_with_args`] to fill them.
 pub fn resolve_uri<'a>(link: &str, config: &'a Config) -> Result<&'a str> {
+    if let Some(tag) = tag_selector(link) {
+        anyhow::bail!("no links tagged '{tag}'");
+    }
+
     if let Some(alias_target) = config.aliases.get(link) {
         return config
             .links
             .get(alias_target)
-            .map(String::as_str)
+            .map(|l| l.url.as_str())
             .with_context(|| {
                 format!("alias '{link}' points to '{alias_target}' which is not in [links]")
             });
@@ -16,72 +23,236 @@ pub fn resolve_uri<'a>(link: &str, config: &'a Config) -> Result<&'a str> {
     config
         .links
         .get(link)
-        .map(String::as_str)
+        .map(|l| l.url.as_str())
         .with_context(|| format!("'{link}' not found in [aliases] or [links]"))
 }
 
+/// Resolve a link or alias to a URL, filling any template placeholders from
+/// `args`.
+pub fn resolve_with_args(link: &str, args: &[&str], config: &Config) -> Result<String> {
+    let url = resolve_uri(link, config)?;
+    template::expand(link, url, args)
+}
+
+/// A name from the command line and the arguments it takes, if it is a
+/// template link.
+#[derive(Debug, PartialEq)]
+pub struct Invocation<'a> {
+    pub name: &'a String,
+    pub args: Vec<&'a str>,
+}
+
+/// Split command-line words into invocations: a template link consumes as
+/// many following words as it has parameters, or all of them for `{*}`.
+pub fn parse_invocations<'a>(words: &'a [String], config: &Config) -> Vec<Invocation<'a>> {
+    let mut invocations = Vec::new();
+    let mut words = words.iter();
+    while let Some(name) = words.next() {
+        let arity = if config.groups.contains_key(name) {
+            Some(0)
+        } else {
+            resolve_uri(name, config).map_or(Some(0), template::arity)
+        };
+        let args = match arity {
+            Some(n) => words.by_ref().take(n).map(String::as_str).collect(),
+            None => words.by_ref().map(String::as_str).collect(),
+        };
+        invocations.push(Invocation { name, args });
+    }
+    invocations
+}
+
 fn open_it(link: &str) -> Result<()> {
     open::that(link).with_context(|| format!("failed to open {link}"))?;
     println!("opening {link}...");
     Ok(())
 }
 
+/// The tag named by a `tag:<name>` or `+<name>` selector.
+pub fn tag_selector(link: &str) -> Option<&str> {
+    link.strip_prefix("tag:")
+        .or_else(|| link.strip_prefix('+'))
+        .filter(|tag| !tag.is_empty())
+}
+
+/// Expand groups (recursively) to their links and aliases and tag selectors
+/// to every link carrying the tag. Each name appears once; a selector that
+/// matches nothing is kept so it can be reported.
+#[deprecated(note = "use `plan`, which also handles template arguments")]
 pub fn expand_groups<'a>(links: &'a [String], config: &'a Config) -> Vec<&'a str> {
     let mut expanded = Vec::new();
     for link in links {
-        if let Some(group_items) = config.groups.get(link.as_str()) {
-            expanded.extend(group_items.iter().map(|s| s.as_str()));
+        let names = if config.groups.contains_key(link.as_str()) {
+            config.group_members(link)
         } else {
-            expanded.push(link.as_str());
+            match tag_selector(link).map(|tag| config.links_tagged(tag).collect::<Vec<_>>()) {
+                Some(tagged) if !tagged.is_empty() => tagged,
+                _ => vec![link.as_str()],
+            }
+        };
+        for name in names {
+            if !expanded.contains(&name) {
+                expanded.push(name);
+            }
         }
     }
     expanded
 }
 
-pub fn open_links(links: &[String], config: &Config) -> Result<()> {
-    let expanded_links = expand_groups(links, config);
+/// How [`open_links`] handles the URLs it resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenMode {
+    /// Open each URL in the default browser.
+    #[default]
+    Open,
+    /// Describe what would open, and how each name was reached, without
+    /// opening anything.
+    DryRun,
+    /// Print each URL on its own line instead of opening it.
+    Print,
+}
+
+/// One URL that opening some words leads to.
+#[derive(Debug)]
+pub struct Target<'a> {
+    /// The link or alias that resolves to the URL.
+    pub name: &'a str,
+    /// The names that lead to it, from the word on the command line through
+    /// groups and aliases to the link, e.g. `["all", "dev", "gh", "github"]`.
+    pub path: Vec<&'a str>,
+    /// The URL, or why there is none.
+    pub url: Result<String>,
+}
 
-    for link in expanded_links {
-        match resolve_uri(link, config) {
-            Ok(uri) => {
-                if let Err(e) = open_it(uri) {
-                    eprintln!("[dkdc] failed to open {link}: {e}");
-                }
+impl Target<'_> {
+    /// The path as `all -> dev -> gh -> github`.
+    pub fn route(&self) -> String {
+        self.path.join(" -> ")
+    }
+}
+
+/// Everything opening `words` would open, in order. Templates take their
+/// arguments, groups expand (recursively) to their links and aliases, and tag
+/// selectors to every link carrying the tag. Each name without arguments
+/// appears once; a selector that matches nothing is kept so it can be
+/// reported.
+pub fn plan<'a>(words: &'a [String], config: &'a Config) -> Vec<Target<'a>> {
+    let mut targets: Vec<Target<'a>> = Vec::new();
+    for invocation in parse_invocations(words, config) {
+        let word = invocation.name.as_str();
+        let paths = if !invocation.args.is_empty() {
+            vec![vec![word]]
+        } else if config.groups.contains_key(word) {
+            config.member_paths(word)
+        } else if let Some(tag) = tag_selector(word) {
+            let tagged: Vec<_> = config
+                .links_tagged(tag)
+                .map(|link| vec![word, link])
+                .collect();
+            if tagged.is_empty() {
+                vec![vec![word]]
+            } else {
+                tagged
+            }
+        } else {
+            vec![vec![word]]
+        };
+
+        for mut path in paths {
+            let name = *path.last().expect("paths are never empty");
+            if invocation.args.is_empty() && targets.iter().any(|t| t.name == name) {
+                continue;
             }
+            if let Some(target) = config.aliases.get(name) {
+                path.push(target);
+            }
+            targets.push(Target {
+                name,
+                path,
+                url: resolve_with_args(name, &invocation.args, config),
+            });
+        }
+    }
+    targets
+}
+
+/// Handle every target `links` expands to, carrying on past the ones that
+/// can't be resolved or opened and failing at the end if there were any.
+pub fn open_links(links: &[String], config: &Config, mode: OpenMode) -> Result<()> {
+    let targets = plan(links, config);
+    let mut failed = 0;
+    for target in &targets {
+        let link = target.name;
+        match &target.url {
+            Ok(uri) => match mode {
+                OpenMode::Open => {
+                    if let Err(e) = open_it(uri) {
+                        eprintln!("[dkdc] failed to open {link}: {e}");
+                        failed += 1;
+                    }
+                }
+                OpenMode::DryRun => println!("would open {uri} ({})", target.route()),
+                OpenMode::Print => println!("{uri}"),
+            },
             Err(e) => {
                 eprintln!("[dkdc] skipping {link}: {e}");
+                failed += 1;
             }
         }
     }
 
+    if failed > 0 {
+        let total = targets.len();
+        let noun = if total == 1 { "link" } else { "links" };
+        anyhow::bail!("{failed} of {total} {noun} failed");
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use crate::config::{IndexMap, Link};
 
     fn test_config() -> Config {
-        let mut aliases = HashMap::new();
+        let mut aliases = IndexMap::new();
         aliases.insert("gh".to_string(), "github".to_string());
         aliases.insert("g".to_string(), "google".to_string());
 
-        let mut links = HashMap::new();
-        links.insert("github".to_string(), "https://github.com".to_string());
-        links.insert("google".to_string(), "https://google.com".to_string());
-        links.insert("rust".to_string(), "https://rust-lang.org".to_string());
+        let mut links = IndexMap::new();
+        links.insert("github".to_string(), Link::new("https://github.com"));
+        links.insert("google".to_string(), Link::new("https://google.com"));
+        let mut rust = Link::new("https://rust-lang.org");
+        rust.tags = vec!["dev".to_string(), "lang".to_string()];
+        links.insert("rust".to_string(), rust);
+        let mut docs = Link::new("https://docs.rs");
+        docs.tags = vec!["dev".to_string()];
+        links.insert("docs".to_string(), docs);
+        links.insert("repo".to_string(), Link::new("https://github.com/{0}"));
+        links.insert(
+            "ddg".to_string(),
+            Link::new("https://duckduckgo.com/?q={*}"),
+        );
+        links.insert(
+            "jira".to_string(),
+            Link::new("https://jira.example.com/browse/{ticket}"),
+        );
 
-        let mut groups = HashMap::new();
+        let mut groups = IndexMap::new();
         groups.insert(
             "dev".to_string(),
             vec!["gh".to_string(), "rust".to_string()],
         );
+        groups.insert(
+            "all".to_string(),
+            vec!["dev".to_string(), "g".to_string(), "rust".to_string()],
+        );
 
         Config {
             aliases,
             links,
             groups,
+            ..Config::default()
         }
     }
 
@@ -129,19 +300,192 @@ mod tests {
         assert!(err.contains("nonexistent"));
     }
 
+    fn names<'a>(links: &'a [String], config: &'a Config) -> Vec<&'a str> {
+        plan(links, config).iter().map(|t| t.name).collect()
+    }
+
     #[test]
     fn test_expand_group() {
         let config = test_config();
         let links = vec!["dev".to_string()];
-        let expanded = expand_groups(&links, &config);
-        assert_eq!(expanded, vec!["gh", "rust"]);
+        assert_eq!(names(&links, &config), vec!["gh", "rust"]);
     }
 
     #[test]
     fn test_mixed_groups_and_links() {
         let config = test_config();
         let links = vec!["dev".to_string(), "google".to_string()];
-        let expanded = expand_groups(&links, &config);
-        assert_eq!(expanded, vec!["gh", "rust", "google"]);
+        assert_eq!(names(&links, &config), vec!["gh", "rust", "google"]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_expand_groups_matches_plan() {
+        let config = test_config();
+        let links = words(&["all", "gh", "+lang", "+nope"]);
+        assert_eq!(expand_groups(&links, &config), names(&links, &config));
+    }
+
+    #[test]
+    fn test_tag_selector() {
+        assert_eq!(tag_selector("tag:work"), Some("work"));
+        assert_eq!(tag_selector("+work"), Some("work"));
+        assert_eq!(tag_selector("work"), None);
+        assert_eq!(tag_selector("+"), None);
+        assert_eq!(tag_selector("tag:"), None);
+    }
+
+    #[test]
+    fn test_expand_tag_selectors() {
+        let config = test_config();
+        let links = vec!["+dev".to_string(), "gh".to_string(), "tag:lang".to_string()];
+        assert_eq!(names(&links, &config), vec!["rust", "docs", "gh"]);
+    }
+
+    #[test]
+    fn test_unmatched_tag_is_reported() {
+        let config = test_config();
+        let links = vec!["+nope".to_string()];
+        let targets = plan(&links, &config);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "+nope");
+        let err = targets[0].url.as_ref().unwrap_err();
+        assert_eq!(err.to_string(), "no links tagged 'nope'");
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_templates_consume_arguments() {
+        let config = test_config();
+        let words = words(&[
+            "repo",
+            "rust-lang/rust",
+            "dev",
+            "jira",
+            "ticket=ABC-1",
+            "gh",
+        ]);
+        let invocations = parse_invocations(&words, &config);
+        let parsed: Vec<_> = invocations
+            .iter()
+            .map(|i| (i.name.as_str(), i.args.clone()))
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                ("repo", vec!["rust-lang/rust"]),
+                ("dev", vec![]),
+                ("jira", vec!["ticket=ABC-1"]),
+                ("gh", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_template() {
+        let config = test_config();
+        let uri = resolve_with_args("repo", &["rust-lang/rust"], &config).unwrap();
+        assert_eq!(uri, "https://github.com/rust-lang/rust");
+        let uri = resolve_with_args("jira", &["ticket=ABC 1"], &config).unwrap();
+        assert_eq!(uri, "https://jira.example.com/browse/ABC%201");
+    }
+
+    #[test]
+    fn test_template_without_arguments_errors() {
+        let config = test_config();
+        let words = words(&["repo"]);
+        let invocations = parse_invocations(&words, &config);
+        assert!(invocations[0].args.is_empty());
+
+        let err = resolve_with_args("repo", &[], &config).unwrap_err();
+        assert!(err.to_string().contains("needs a value for {0}"));
+    }
+
+    #[test]
+    fn test_search_link_consumes_rest() {
+        let config = test_config();
+        let words = words(&["gh", "ddg", "rust", "async", "traits"]);
+        let invocations = parse_invocations(&words, &config);
+        assert_eq!(invocations.len(), 2);
+        assert_eq!(invocations[1].args, vec!["rust", "async", "traits"]);
+
+        let uri = resolve_with_args("ddg", &invocations[1].args, &config).unwrap();
+        assert_eq!(uri, "https://duckduckgo.com/?q=rust+async+traits");
+    }
+
+    fn plan_of(words: &[String], config: &Config) -> Vec<(String, Result<String, String>)> {
+        plan(words, config)
+            .into_iter()
+            .map(|t| (t.route(), t.url.map_err(|e| e.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan() {
+        let config = test_config();
+        // "+lang" only reaches rust, which "dev" already opens
+        let words = words(&["dev", "repo", "rust-lang/rust", "nope", "+lang"]);
+        assert_eq!(
+            plan_of(&words, &config),
+            vec![
+                (
+                    "dev -> gh -> github".to_string(),
+                    Ok("https://github.com".to_string())
+                ),
+                (
+                    "dev -> rust".to_string(),
+                    Ok("https://rust-lang.org".to_string())
+                ),
+                (
+                    "repo".to_string(),
+                    Ok("https://github.com/rust-lang/rust".to_string())
+                ),
+                (
+                    "nope".to_string(),
+                    Err("'nope' not found in [aliases] or [links]".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_follows_nested_groups() {
+        let mut config = test_config();
+        // A cycle back to "all" is cut rather than followed
+        config.groups["dev"].push("all".to_string());
+        let words = words(&["all"]);
+        let routes: Vec<_> = plan(&words, &config).iter().map(Target::route).collect();
+        assert_eq!(
+            routes,
+            vec![
+                "all -> dev -> gh -> github",
+                "all -> dev -> rust",
+                "all -> g -> google"
+            ]
+        );
+        let names: Vec<_> = plan(&words, &config).iter().map(|t| t.name).collect();
+        assert_eq!(names, config.group_members("all"));
+    }
+
+    #[test]
+    fn test_open_links_fails_if_any_target_fails() {
+        let config = test_config();
+        let words = vec!["nope".to_string()];
+        assert!(open_links(&words, &config, OpenMode::Print).is_err());
+
+        let words = vec!["gh".to_string(), "nope".to_string()];
+        let err = open_links(&words, &config, OpenMode::DryRun).unwrap_err();
+        assert_eq!(err.to_string(), "1 of 2 links failed");
+        assert!(open_links(&words[..1], &config, OpenMode::Print).is_ok());
+    }
+
+    #[test]
+    fn test_expand_nested_group_dedups() {
+        let config = test_config();
+        let links = vec!["all".to_string(), "gh".to_string()];
+        assert_eq!(names(&links, &config), vec!["gh", "rust", "g"]);
     }
 }
@@ -0,0 +1,574 @@
+use anyhow::{Context, Result};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use toml::value::Datetime;
+
+use crate::config::{Config, IndexMap, Link, DEFAULT_CONFIG};
+use crate::diagnostic::Diagnostic;
+use crate::doctor;
+use crate::storage::Storage;
+use crate::toml_storage::TomlStorage;
+
+const DB_FILENAME: &str = "links.db";
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how
+/// many have run, so only ever append to this list.
+const MIGRATIONS: &[&str] = &[
+    r#"
+CREATE TABLE links (
+    name TEXT PRIMARY KEY,
+    url TEXT NOT NULL
+);
+CREATE TABLE aliases (
+    name TEXT PRIMARY KEY,
+    target TEXT NOT NULL
+);
+CREATE INDEX aliases_target ON aliases (target);
+CREATE TABLE groups (
+    name TEXT PRIMARY KEY
+);
+CREATE TABLE group_entries (
+    group_name TEXT NOT NULL REFERENCES groups (name) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    entry TEXT NOT NULL,
+    PRIMARY KEY (group_name, position)
+);
+CREATE INDEX group_entries_entry ON group_entries (entry);
+"#,
+    r#"
+ALTER TABLE links ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE aliases ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE groups ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+UPDATE links SET position = rowid;
+UPDATE aliases SET position = rowid;
+UPDATE groups SET position = rowid;
+"#,
+    r#"
+ALTER TABLE links ADD COLUMN description TEXT;
+ALTER TABLE links ADD COLUMN created TEXT;
+ALTER TABLE links ADD COLUMN updated TEXT;
+CREATE TABLE link_tags (
+    link TEXT NOT NULL REFERENCES links (name) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (link, position)
+);
+CREATE INDEX link_tags_tag ON link_tags (tag);
+"#,
+];
+
+const SELECT_LINKS: &str = "SELECT name, url, description, created, updated FROM links";
+
+pub struct SqliteStorage {
+    path: PathBuf,
+    conn: Mutex<Option<Connection>>,
+}
+
+impl SqliteStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            conn: Mutex::new(None),
+        }
+    }
+
+    /// Default database path: ~/.config/dkdc/links/links.db
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(TomlStorage::default_path()?.with_file_name(DB_FILENAME))
+    }
+
+    pub fn with_default_path() -> Result<Self> {
+        Ok(Self::new(Self::default_path()?))
+    }
+
+    /// Run `f` against the connection, opening and migrating it on first use.
+    fn with_conn<T>(&self, f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+        let mut guard = self.conn.lock().unwrap();
+        let conn = match guard.as_mut() {
+            Some(conn) => conn,
+            None => {
+                let mut conn = Connection::open(&self.path)
+                    .with_context(|| format!("Failed to open database {}", self.path.display()))?;
+                conn.pragma_update(None, "foreign_keys", true)?;
+                migrate(&mut conn)?;
+                guard.insert(conn)
+            }
+        };
+        f(conn)
+    }
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction()?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(sql)
+            .with_context(|| format!("Failed to apply database migration {}", i + 1))?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// A link from a `SELECT_LINKS` row, without its tags.
+fn link_from_row(row: &Row) -> rusqlite::Result<(String, Link)> {
+    let link = Link {
+        url: row.get(1)?,
+        description: row.get(2)?,
+        tags: Vec::new(),
+        created: datetime_column(row, 3)?,
+        updated: datetime_column(row, 4)?,
+    };
+    Ok((row.get(0)?, link))
+}
+
+fn datetime_column(row: &Row, index: usize) -> rusqlite::Result<Option<Datetime>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|s| s.parse::<Datetime>())
+        .transpose()
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+fn read_config(conn: &Connection) -> Result<Config> {
+    let mut config = Config::default();
+
+    let mut stmt = conn.prepare(&format!("{SELECT_LINKS} ORDER BY position"))?;
+    for row in stmt.query_map([], link_from_row)? {
+        let (name, link) = row?;
+        config.links.insert(name, link);
+    }
+
+    let mut stmt = conn.prepare("SELECT link, tag FROM link_tags ORDER BY link, position")?;
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+        let (name, tag) = row?;
+        if let Some(link) = config.links.get_mut(&name) {
+            link.tags.push(tag);
+        }
+    }
+
+    let mut stmt = conn.prepare("SELECT name, target FROM aliases ORDER BY position")?;
+    for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        let (name, target) = row?;
+        config.aliases.insert(name, target);
+    }
+
+    let mut stmt = conn.prepare("SELECT name FROM groups ORDER BY position")?;
+    for row in stmt.query_map([], |row| row.get(0))? {
+        config.groups.insert(row?, Vec::new());
+    }
+
+    let mut stmt =
+        conn.prepare("SELECT group_name, entry FROM group_entries ORDER BY group_name, position")?;
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+        let (group, entry) = row?;
+        if let Some(entries) = config.groups.get_mut(&group) {
+            entries.push(entry);
+        }
+    }
+
+    Ok(config)
+}
+
+/// Write only the rows that differ between `before` and `after`.
+fn write_diff(conn: &Connection, before: &Config, after: &Config) -> Result<()> {
+    for name in before.links.keys() {
+        if !after.links.contains_key(name) {
+            conn.execute("DELETE FROM links WHERE name = ?1", [name])?;
+        }
+    }
+    for (position, (name, link)) in after.links.iter().enumerate() {
+        if before.links.get(name) == Some(link) {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO links (name, url, description, created, updated, position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (name) DO UPDATE SET url = excluded.url,
+                 description = excluded.description,
+                 created = excluded.created,
+                 updated = excluded.updated",
+            params![
+                name,
+                link.url,
+                link.description,
+                link.created.as_ref().map(ToString::to_string),
+                link.updated.as_ref().map(ToString::to_string),
+                position
+            ],
+        )?;
+        conn.execute("DELETE FROM link_tags WHERE link = ?1", [name])?;
+        for (position, tag) in link.tags.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO link_tags (link, position, tag) VALUES (?1, ?2, ?3)",
+                params![name, position, tag],
+            )?;
+        }
+    }
+    write_positions(conn, "links", &before.links, &after.links)?;
+
+    for name in before.aliases.keys() {
+        if !after.aliases.contains_key(name) {
+            conn.execute("DELETE FROM aliases WHERE name = ?1", [name])?;
+        }
+    }
+    for (position, (name, target)) in after.aliases.iter().enumerate() {
+        if before.aliases.get(name) != Some(target) {
+            conn.execute(
+                "INSERT INTO aliases (name, target, position) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (name) DO UPDATE SET target = excluded.target",
+                params![name, target, position],
+            )?;
+        }
+    }
+    write_positions(conn, "aliases", &before.aliases, &after.aliases)?;
+
+    for name in before.groups.keys() {
+        if !after.groups.contains_key(name) {
+            conn.execute("DELETE FROM groups WHERE name = ?1", [name])?;
+        }
+    }
+    write_positions(conn, "groups", &before.groups, &after.groups)?;
+    for (position, (name, entries)) in after.groups.iter().enumerate() {
+        if before.groups.get(name) == Some(entries) {
+            continue;
+        }
+        conn.execute(
+            "INSERT OR IGNORE INTO groups (name, position) VALUES (?1, ?2)",
+            params![name, position],
+        )?;
+        conn.execute("DELETE FROM group_entries WHERE group_name = ?1", [name])?;
+        for (position, entry) in entries.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO group_entries (group_name, position, entry) VALUES (?1, ?2, ?3)",
+                params![name, position, entry],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renumber rows of `table` whose position moved between `before` and `after`.
+fn write_positions<V>(
+    conn: &Connection,
+    table: &str,
+    before: &IndexMap<String, V>,
+    after: &IndexMap<String, V>,
+) -> Result<()> {
+    let sql = format!("UPDATE {table} SET position = ?2 WHERE name = ?1");
+    for (position, name) in after.keys().enumerate() {
+        if before.get_index_of(name).is_some_and(|i| i != position) {
+            conn.execute(&sql, params![name, position])?;
+        }
+    }
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> Result<Config> {
+        let config = self.with_conn(|conn| read_config(conn))?;
+
+        for diagnostic in config.validate() {
+            eprintln!("{}", diagnostic.render(Some(&self.path), None));
+        }
+
+        Ok(config)
+    }
+
+    fn save(&self, config: &Config) -> Result<()> {
+        self.with_conn(|conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let before = read_config(&tx)?;
+            write_diff(&tx, &before, config)?;
+            tx.commit().context("Failed to write database")
+        })
+    }
+
+    fn init(&self) -> Result<()> {
+        if self.path.exists() {
+            return self.with_conn(|_| Ok(()));
+        }
+        let db_dir = self
+            .path
+            .parent()
+            .context("Invalid database path: no parent directory")?;
+        fs::create_dir_all(db_dir).context("Failed to create database directory")?;
+        let config: Config = toml::from_str(DEFAULT_CONFIG)?;
+        self.save(&config)
+    }
+
+    fn backend_name(&self) -> &str {
+        "sqlite"
+    }
+
+    fn stored(&self) -> Result<Config> {
+        self.with_conn(|conn| read_config(conn))
+    }
+
+    fn diagnostics(&self) -> Result<Vec<Diagnostic>> {
+        Ok(doctor::check(&self.with_conn(|conn| read_config(conn))?))
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    /// Runs the whole read-modify-write in one transaction and writes only
+    /// the rows `f` changed.
+    fn update(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        self.with_conn(|conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let before = read_config(&tx)?;
+            let mut after = before.clone();
+            f(&mut after)?;
+            write_diff(&tx, &before, &after)?;
+            tx.commit().context("Failed to write database")
+        })
+    }
+
+    fn get_link(&self, name: &str) -> Result<Option<Link>> {
+        self.with_conn(|conn| {
+            let sql = format!("{SELECT_LINKS} WHERE name = ?1");
+            let Some((_, mut link)) = conn.query_row(&sql, [name], link_from_row).optional()?
+            else {
+                return Ok(None);
+            };
+            let mut stmt =
+                conn.prepare("SELECT tag FROM link_tags WHERE link = ?1 ORDER BY position")?;
+            link.tags = stmt
+                .query_map([name], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(Some(link))
+        })
+    }
+
+    fn get_alias(&self, name: &str) -> Result<Option<String>> {
+        self.with_conn(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT target FROM aliases WHERE name = ?1",
+                    [name],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })
+    }
+
+    fn get_group(&self, name: &str) -> Result<Option<Vec<String>>> {
+        self.with_conn(|conn| {
+            let exists = conn
+                .query_row("SELECT 1 FROM groups WHERE name = ?1", [name], |_| Ok(()))
+                .optional()?
+                .is_some();
+            if !exists {
+                return Ok(None);
+            }
+            let mut stmt = conn.prepare(
+                "SELECT entry FROM group_entries WHERE group_name = ?1 ORDER BY position",
+            )?;
+            let entries = stmt
+                .query_map([name], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(Some(entries))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage_in(dir: &tempfile::TempDir) -> SqliteStorage {
+        SqliteStorage::new(dir.path().join("links.db"))
+    }
+
+    #[test]
+    fn test_default_path() {
+        let path = SqliteStorage::default_path().unwrap();
+        assert!(path.ends_with(".config/dkdc/links/links.db"));
+    }
+
+    #[test]
+    fn test_init_seeds_default_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+
+        let config = storage.load().unwrap();
+        let default: Config = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(config.links, default.links);
+        assert_eq!(config.aliases, default.aliases);
+        assert_eq!(config.groups, default.groups);
+    }
+
+    #[test]
+    fn test_init_does_not_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.save(&Config::default()).unwrap();
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        drop(storage);
+
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+        let config = storage.load().unwrap();
+        assert_eq!(config.links.len(), 1);
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+
+        let mut config = Config::default();
+        config.add_link("github", "https://github.com").unwrap();
+        config.add_alias("gh", "github").unwrap();
+        config
+            .add_group("dev", vec!["gh".to_string(), "github".to_string()])
+            .unwrap();
+        storage.save(&config).unwrap();
+
+        let reloaded = storage.load().unwrap();
+        assert_eq!(config.links, reloaded.links);
+        assert_eq!(config.aliases, reloaded.aliases);
+        assert_eq!(config.groups, reloaded.groups);
+
+        config.links.clear();
+        config.aliases.clear();
+        config.groups.clear();
+        storage.save(&config).unwrap();
+        let reloaded = storage.load().unwrap();
+        assert!(reloaded.links.is_empty());
+        assert!(reloaded.groups.is_empty());
+    }
+
+    #[test]
+    fn test_indexed_lookups() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+
+        assert_eq!(
+            storage.get_link("link1").unwrap().map(|l| l.url).as_deref(),
+            Some("https://crates.io/crates/dkdc-links")
+        );
+        assert_eq!(storage.get_alias("a1").unwrap().as_deref(), Some("link1"));
+        assert_eq!(
+            storage.get_group("dev").unwrap(),
+            Some(vec!["alias1".to_string(), "alias2".to_string()])
+        );
+        assert!(storage.get_link("nope").unwrap().is_none());
+        assert!(storage.get_group("nope").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_link_metadata_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+
+        let link = Link {
+            url: "https://github.com".to_string(),
+            description: Some("code hosting".to_string()),
+            tags: vec!["dev".to_string(), "work".to_string()],
+            created: Some("2024-01-02T03:04:05Z".parse().unwrap()),
+            updated: None,
+        };
+        storage
+            .update(&mut |config| config.put_link("github", link.clone()))
+            .unwrap();
+        assert_eq!(storage.get_link("github").unwrap(), Some(link.clone()));
+
+        storage.rename_link("github", "gh").unwrap();
+        let config = storage.load().unwrap();
+        assert_eq!(config.links["gh"].tags, link.tags);
+        assert_eq!(config.links["gh"].created, link.created);
+    }
+
+    #[test]
+    fn test_rename_cascades_through_update() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+
+        storage.rename_alias("alias1", "first").unwrap();
+        storage.rename_link("link1", "crate").unwrap();
+
+        assert_eq!(storage.get_alias("a1").unwrap().as_deref(), Some("crate"));
+        assert_eq!(
+            storage.get_group("dev").unwrap(),
+            Some(vec!["first".to_string(), "alias2".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_order_is_preserved() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+
+        storage.put_link("zzz", "https://example.com/z").unwrap();
+        storage.put_link("aaa", "https://example.com/a").unwrap();
+        storage.rename_link("link1", "first").unwrap();
+
+        let config = storage.load().unwrap();
+        let names: Vec<_> = config.links.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["first", "link2", "zzz", "aaa"]);
+    }
+
+    #[test]
+    fn test_failed_update_rolls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage_in(&dir);
+        storage.init().unwrap();
+
+        assert!(storage.put_alias("x", "missing").is_err());
+        assert!(storage.get_alias("x").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        storage_in(&dir).init().unwrap();
+
+        let mut conn = Connection::open(dir.path().join("links.db")).unwrap();
+        migrate(&mut conn).unwrap();
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_migrates_v1_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("links.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(MIGRATIONS[0]).unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+            conn.execute_batch(
+                "INSERT INTO links (name, url) VALUES ('b', 'https://b.example.com');
+                 INSERT INTO links (name, url) VALUES ('a', 'https://a.example.com');",
+            )
+            .unwrap();
+        }
+
+        let config = SqliteStorage::new(path).load().unwrap();
+        let names: Vec<_> = config.links.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_backend_name() {
+        let storage = SqliteStorage::new(PathBuf::from("/tmp/test.db"));
+        assert_eq!(storage.backend_name(), "sqlite");
+    }
+}
@@ -1,11 +1,42 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
 
-use crate::config::Config;
+use crate::config::{Config, DeleteMode, Dependent, Link};
+use crate::diagnostic::Diagnostic;
+use crate::doctor;
+use crate::git_storage::{Commit, GitStorage};
+use crate::journal::JournaledStorage;
+use crate::layered_storage::{LayeredStorage, Origin};
+use crate::toml_storage::TomlStorage;
+
+/// Returned by `Storage::save` when the stored data changed since it was
+/// last loaded. Recover it from an `anyhow::Error` with `downcast_ref`.
+#[derive(Debug)]
+pub struct ConflictError {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} changed on disk since it was loaded; reload and try again",
+            self.path.display()
+        )
+    }
+}
+
+impl std::error::Error for ConflictError {}
 
 /// Backend-agnostic storage for link data.
 ///
-/// Operates on `Config` as a whole — fine-grained CRUD can be added
-/// as default methods later.
+/// Backends implement whole-`Config` `load`/`save`; the fine-grained CRUD
+/// methods default to a load-mutate-save cycle through `update` and can be
+/// overridden by backends that support targeted writes.
 pub trait Storage: Send + Sync {
     /// Load the full config (aliases, links, groups).
     fn load(&self) -> Result<Config>;
@@ -23,4 +54,304 @@ pub trait Storage: Send + Sync {
     fn path(&self) -> Option<&std::path::Path> {
         None
     }
+
+    /// The config as stored right now, without reporting its problems or
+    /// counting as a load for conflict detection.
+    fn stored(&self) -> Result<Config> {
+        self.load()
+    }
+
+    /// Take the lock that serializes changes across processes, held until
+    /// the guard drops. `None` for backends without a file to lock.
+    fn lock(&self) -> Result<Option<FileLock>> {
+        self.path()
+            .map(|path| FileLock::acquire(sidecar(path, ".lock")))
+            .transpose()
+    }
+
+    /// Every problem [`doctor::check`] finds in the stored config. Backends
+    /// that keep the source text fill in spans.
+    fn diagnostics(&self) -> Result<Vec<Diagnostic>> {
+        Ok(doctor::check(&self.load()?))
+    }
+
+    /// Load the config, apply `f`, and save the result. Nothing is saved if
+    /// `f` fails.
+    fn update(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        let mut config = self.load()?;
+        f(&mut config)?;
+        self.save(&config)
+    }
+
+    /// Delete a link, alias or group by name; see [`Config::delete`].
+    fn delete(&self, name: &str, mode: DeleteMode) -> Result<Vec<Dependent>> {
+        let mut dependents = Vec::new();
+        self.update(&mut |config| {
+            dependents = config.delete(name, mode)?;
+            Ok(())
+        })?;
+        Ok(dependents)
+    }
+
+    // -- Links ---------------------------------------------------------------
+
+    fn get_link(&self, name: &str) -> Result<Option<Link>> {
+        Ok(self.load()?.links.get(name).cloned())
+    }
+
+    fn put_link(&self, name: &str, url: &str) -> Result<()> {
+        self.update(&mut |config| config.add_link(name, url))
+    }
+
+    fn delete_link(&self, name: &str) -> Result<()> {
+        self.update(&mut |config| config.remove_link(name).map(drop))
+    }
+
+    fn rename_link(&self, old: &str, new: &str) -> Result<()> {
+        self.update(&mut |config| config.rename_link(old, new))
+    }
+
+    // -- Aliases -------------------------------------------------------------
+
+    fn get_alias(&self, name: &str) -> Result<Option<String>> {
+        Ok(self.load()?.aliases.get(name).cloned())
+    }
+
+    fn put_alias(&self, alias: &str, target: &str) -> Result<()> {
+        self.update(&mut |config| config.add_alias(alias, target))
+    }
+
+    fn delete_alias(&self, name: &str) -> Result<()> {
+        self.update(&mut |config| config.remove_alias(name).map(drop))
+    }
+
+    fn rename_alias(&self, old: &str, new: &str) -> Result<()> {
+        self.update(&mut |config| config.rename_alias(old, new))
+    }
+
+    // -- Groups --------------------------------------------------------------
+
+    fn get_group(&self, name: &str) -> Result<Option<Vec<String>>> {
+        Ok(self.load()?.groups.get(name).cloned())
+    }
+
+    fn put_group(&self, name: &str, entries: &[String]) -> Result<()> {
+        self.update(&mut |config| config.add_group(name, entries.to_vec()))
+    }
+
+    fn delete_group(&self, name: &str) -> Result<()> {
+        self.update(&mut |config| config.remove_group(name).map(drop))
+    }
+
+    fn rename_group(&self, old: &str, new: &str) -> Result<()> {
+        self.update(&mut |config| config.rename_group(old, new))
+    }
+
+    // -- Capabilities --------------------------------------------------------
+
+    /// The backend's version control, if it keeps the config in git.
+    fn versioned(&self) -> Option<&dyn Versioned> {
+        None
+    }
+
+    /// The backend's layers, if it merges several config files.
+    fn layered(&self) -> Option<&dyn Layered> {
+        None
+    }
+}
+
+/// A backend that keeps the config under version control.
+pub trait Versioned {
+    /// Like [`Storage::update`], but record the change as `message` rather
+    /// than a description of what changed, e.g. for an undo.
+    fn update_as(&self, message: &str, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()>;
+
+    /// The latest `limit` commits that changed the config, newest first.
+    fn history(&self, limit: usize) -> Result<Vec<Commit>>;
+
+    /// Pull changes from the remote and push local ones, returning a
+    /// summary of what moved.
+    fn sync(&self) -> Result<String>;
+}
+
+/// A backend that merges several config files into one.
+pub trait Layered {
+    /// Which layer each entry of the merged config comes from.
+    fn origins(&self) -> Result<Vec<Origin>>;
+}
+
+/// Open a storage backend from a spec like `toml:<path>`, `git:<path>` or
+/// `sqlite:<path>`, wrapped in a [`JournaledStorage`] so changes can be
+/// undone. A bare backend name (`toml`, `git`, `sqlite`) uses that backend's
+/// default path. `layered` finds its files itself and writes to the topmost
+/// one it can; `layered:<layer>` names the layer, e.g. `layered:user`.
+pub fn open(spec: &str) -> Result<JournaledStorage> {
+    Ok(JournaledStorage::new(open_backend(spec)?))
+}
+
+fn open_backend(spec: &str) -> Result<Box<dyn Storage>> {
+    let (backend, path) = match spec.split_once(':') {
+        Some((backend, path)) => (backend, Some(expand_home(path)?)),
+        None => (spec, None),
+    };
+
+    match backend {
+        "toml" => Ok(Box::new(match path {
+            Some(path) => TomlStorage::new(path),
+            None => TomlStorage::with_default_path()?,
+        })),
+        "git" => Ok(Box::new(match path {
+            Some(path) => GitStorage::new(path),
+            None => GitStorage::with_default_path()?,
+        })),
+        "layered" => {
+            let target = path
+                .as_deref()
+                .map(|layer| layer.to_str().context("invalid layer name"))
+                .transpose()?;
+            Ok(Box::new(LayeredStorage::discover(target)?))
+        }
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Box::new(match path {
+            Some(path) => crate::sqlite_storage::SqliteStorage::new(path),
+            None => crate::sqlite_storage::SqliteStorage::with_default_path()?,
+        })),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => anyhow::bail!("sqlite storage requires the `sqlite` feature"),
+        other => anyhow::bail!(
+            "unknown storage backend '{other}' (expected toml, git, layered or sqlite)"
+        ),
+    }
+}
+
+thread_local! {
+    /// Lock files this thread holds.
+    static HELD: RefCell<HashSet<PathBuf>> = RefCell::new(HashSet::new());
+}
+
+/// An advisory lock on a `.lock` file that is deleted again on release.
+/// Taking a lock this thread already holds succeeds at once, so a wrapper
+/// can hold its backend's lock around calls into the backend.
+pub struct FileLock {
+    held: Option<(File, PathBuf)>,
+}
+
+impl FileLock {
+    pub(crate) fn acquire(path: PathBuf) -> Result<Self> {
+        if HELD.with(|held| held.borrow().contains(&path)) {
+            return Ok(Self { held: None });
+        }
+        loop {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+                .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+            file.lock().context("Failed to lock config file")?;
+            // The holder we waited for may have removed the file; then the
+            // lock is on a file nobody else will open, so start over
+            if is_same_file(&file, &path) {
+                HELD.with(|held| held.borrow_mut().insert(path.clone()));
+                return Ok(Self {
+                    held: Some((file, path)),
+                });
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let Some((file, path)) = self.held.take() else {
+            return;
+        };
+        // Remove the file while still holding the lock; waiters notice and
+        // retry. Windows can't delete an open file, so it stays there.
+        if cfg!(unix) {
+            let _ = fs::remove_file(&path);
+        }
+        let _ = file.unlock();
+        HELD.with(|held| held.borrow_mut().remove(&path));
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, _path: &Path) -> bool {
+    true
+}
+
+/// `path` with `suffix` appended to its file name, for files that live next
+/// to the stored data.
+pub(crate) fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn expand_home(path: &str) -> Result<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => Ok(dirs::home_dir()
+            .context("Failed to get home directory")?
+            .join(rest)),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_toml_with_path() {
+        let storage = open("toml:/tmp/links.toml").unwrap();
+        assert_eq!(storage.backend_name(), "toml");
+        assert_eq!(
+            storage.path(),
+            Some(std::path::Path::new("/tmp/links.toml"))
+        );
+    }
+
+    #[test]
+    fn test_open_expands_home() {
+        let storage = open("toml:~/links.toml").unwrap();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(storage.path(), Some(home.join("links.toml").as_path()));
+    }
+
+    #[test]
+    fn test_open_bare_backend_uses_default_path() {
+        let storage = open("toml").unwrap();
+        assert_eq!(
+            storage.path(),
+            Some(TomlStorage::default_path().unwrap().as_path())
+        );
+    }
+
+    #[test]
+    fn test_open_unknown_backend_errors() {
+        assert!(open("postgres:whatever").is_err());
+    }
+
+    #[test]
+    fn test_lock_is_reentrant_on_one_thread() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml.lock");
+        let outer = FileLock::acquire(path.clone()).unwrap();
+        let inner = FileLock::acquire(path.clone()).unwrap();
+        drop(inner);
+        assert!(path.exists());
+        drop(outer);
+        assert_eq!(path.exists(), !cfg!(unix));
+        FileLock::acquire(path).unwrap();
+    }
 }
@@ -23,3 +23,16 @@ pub fn err_alias_target_missing(target: &str) -> String {
 pub fn err_group_entries_missing(missing: &[&str]) -> String {
     format!("group entries not found: {}", missing.join(", "))
 }
+
+pub fn err_name_taken(name: &str, section: &str) -> String {
+    format!("'{name}' already exists in [{section}]; names must be unique across links, aliases and groups")
+}
+
+pub fn err_has_dependents(name: &str, dependents: &[impl std::fmt::Display]) -> String {
+    let list: Vec<String> = dependents.iter().map(ToString::to_string).collect();
+    format!("'{name}' is still used by {}", list.join(", "))
+}
+
+pub fn err_group_cycle(cycle: &str) -> String {
+    format!("group would contain itself: {cycle}")
+}
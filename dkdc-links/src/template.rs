@@ -0,0 +1,299 @@
+//! Link URL templates: `{0}`, `{1}`, ... for positional parameters,
+//! `{name}` for named ones and `{*}` for a search query made of all the
+//! remaining arguments, filled from the command line.
+
+use anyhow::Result;
+
+/// A placeholder in a URL template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Param {
+    Index(usize),
+    Name(String),
+    /// Every remaining argument, joined with spaces.
+    Rest,
+}
+
+impl std::fmt::Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Param::Index(i) => write!(f, "{{{i}}}"),
+            Param::Name(name) => write!(f, "{{{name}}}"),
+            Param::Rest => f.write_str("{*}"),
+        }
+    }
+}
+
+/// A piece of a parsed template.
+enum Part<'a> {
+    Text(&'a str),
+    Param(Param),
+}
+
+fn parse(url: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut text = 0;
+    let mut pos = 0;
+    while let Some(open) = url[pos..].find('{').map(|i| pos + i) {
+        let Some(close) = url[open..].find('}').map(|i| open + i) else {
+            break;
+        };
+        match placeholder(&url[open + 1..close]) {
+            Some(param) => {
+                parts.push(Part::Text(&url[text..open]));
+                parts.push(Part::Param(param));
+                text = close + 1;
+                pos = close + 1;
+            }
+            // Not a placeholder; keep the brace as text
+            None => pos = open + 1,
+        }
+    }
+    parts.push(Part::Text(&url[text..]));
+    parts
+}
+
+fn placeholder(inner: &str) -> Option<Param> {
+    if inner.is_empty() {
+        None
+    } else if inner == "*" {
+        Some(Param::Rest)
+    } else if inner.bytes().all(|b| b.is_ascii_digit()) {
+        inner.parse().ok().map(Param::Index)
+    } else if inner
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    {
+        Some(Param::Name(inner.to_string()))
+    } else {
+        None
+    }
+}
+
+/// The parameters `url` takes, in the order positional arguments fill them:
+/// indexed ones by index, then named ones in order of first appearance, then
+/// `{*}` if present.
+pub fn params(url: &str) -> Vec<Param> {
+    let mut indexed = Vec::new();
+    let mut named = Vec::new();
+    let mut rest = None;
+    for part in parse(url) {
+        match part {
+            Part::Param(Param::Index(i)) if !indexed.contains(&i) => indexed.push(i),
+            Part::Param(p @ Param::Name(_)) if !named.contains(&p) => named.push(p),
+            Part::Param(Param::Rest) => rest = Some(Param::Rest),
+            _ => {}
+        }
+    }
+    indexed.sort_unstable();
+    indexed
+        .into_iter()
+        .map(Param::Index)
+        .chain(named)
+        .chain(rest)
+        .collect()
+}
+
+/// How many command-line words a link with this URL consumes, or `None` if
+/// it takes all the remaining ones.
+pub fn arity(url: &str) -> Option<usize> {
+    let params = params(url);
+    if params.contains(&Param::Rest) {
+        None
+    } else {
+        Some(params.len())
+    }
+}
+
+/// Fill the placeholders in `url` for the link `name`. Arguments of the form
+/// `key=value` fill the named parameter `key`; the rest fill the remaining
+/// parameters in order, and whatever is left over becomes the `{*}` query.
+/// Values are percent-encoded, keeping `/`; the query is form-encoded.
+pub fn expand(name: &str, url: &str, args: &[&str]) -> Result<String> {
+    let params = params(url);
+    let has_rest = params.last() == Some(&Param::Rest);
+    let slots = params.len() - usize::from(has_rest);
+    let mut values: Vec<Option<&str>> = vec![None; slots];
+
+    let mut positional = Vec::new();
+    for arg in args {
+        let named = arg.split_once('=').and_then(|(key, value)| {
+            let i = params
+                .iter()
+                .position(|p| matches!(p, Param::Name(n) if n == key))?;
+            Some((i, value))
+        });
+        match named {
+            Some((i, value)) if values[i].is_none() => values[i] = Some(value),
+            _ => positional.push(*arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    for value in values.iter_mut().filter(|v| v.is_none()) {
+        *value = positional.next();
+    }
+    let rest: Vec<&str> = positional.collect();
+    if let Some(extra) = rest.first().filter(|_| !has_rest) {
+        anyhow::bail!(
+            "'{name}' takes {} argument(s), got extra '{extra}'",
+            params.len()
+        );
+    }
+    let rest = rest.join(" ");
+
+    let missing: Vec<String> = params
+        .iter()
+        .zip(&values)
+        .filter(|(_, v)| v.is_none())
+        .map(|(p, _)| p.to_string())
+        .collect();
+    if !missing.is_empty() {
+        let usage: Vec<String> = params.iter().map(usage).collect();
+        anyhow::bail!(
+            "'{name}' needs a value for {}; usage: dkdc-links {name} {}",
+            missing.join(", "),
+            usage.join(" ")
+        );
+    }
+
+    let mut out = String::with_capacity(url.len());
+    for part in parse(url) {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Param(Param::Rest) => out.push_str(&encode_query(&rest)),
+            Part::Param(param) => {
+                let i = params
+                    .iter()
+                    .position(|p| *p == param)
+                    .expect("known param");
+                out.push_str(&encode(values[i].expect("filled above")));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn usage(param: &Param) -> String {
+    match param {
+        Param::Index(i) => format!("<{i}>"),
+        Param::Name(name) => format!("{name}=<value>"),
+        Param::Rest => "<query...>".to_string(),
+    }
+}
+
+/// Percent-encode everything but unreserved characters and `/`.
+pub fn encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Form-encode a query: spaces become `+` and everything but unreserved
+/// characters is percent-encoded.
+pub fn encode_query(value: &str) -> String {
+    value
+        .split(' ')
+        .map(|word| encode(word).replace('/', "%2F"))
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params() {
+        assert!(params("https://github.com").is_empty());
+        assert_eq!(params("https://github.com/{0}"), vec![Param::Index(0)]);
+        assert_eq!(
+            params("https://x.com/{user}/{1}/{0}/{user}"),
+            vec![
+                Param::Index(0),
+                Param::Index(1),
+                Param::Name("user".to_string())
+            ]
+        );
+        // Braces that aren't placeholders are left alone
+        assert!(params("https://x.com/{}/{a b}/{").is_empty());
+    }
+
+    #[test]
+    fn test_expand_positional() {
+        let url = expand("gh", "https://github.com/{0}", &["rust-lang/rust"]).unwrap();
+        assert_eq!(url, "https://github.com/rust-lang/rust");
+    }
+
+    #[test]
+    fn test_expand_named() {
+        let template = "https://jira.example.com/browse/{ticket}";
+        let url = expand("jira", template, &["ticket=ABC-123"]).unwrap();
+        assert_eq!(url, "https://jira.example.com/browse/ABC-123");
+        // Named parameters can also be filled positionally
+        let url = expand("jira", template, &["ABC-123"]).unwrap();
+        assert_eq!(url, "https://jira.example.com/browse/ABC-123");
+    }
+
+    #[test]
+    fn test_expand_encodes_values() {
+        let url = expand("s", "https://x.com/{0}?q={q}", &["a b", "q=c&d=é"]).unwrap();
+        assert_eq!(url, "https://x.com/a%20b?q=c%26d%3D%C3%A9");
+    }
+
+    #[test]
+    fn test_expand_missing_param_errors() {
+        let err = expand("jira", "https://x.com/{project}/{ticket}", &["ticket=1"])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "'jira' needs a value for {project}; usage: dkdc-links jira project=<value> ticket=<value>"
+        );
+    }
+
+    #[test]
+    fn test_expand_extra_arg_errors() {
+        let err = expand("gh", "https://github.com/{0}", &["a", "b"]).unwrap_err();
+        assert!(err.to_string().contains("extra 'b'"));
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("rust-lang/rust"), "rust-lang/rust");
+        assert_eq!(encode("a b+c"), "a%20b%2Bc");
+    }
+
+    #[test]
+    fn test_rest_param() {
+        let template = "https://duckduckgo.com/?q={*}";
+        assert_eq!(params(template), vec![Param::Rest]);
+        assert_eq!(arity(template), None);
+        assert_eq!(arity("https://github.com/{0}"), Some(1));
+
+        let url = expand("ddg", template, &["rust", "async", "traits"]).unwrap();
+        assert_eq!(url, "https://duckduckgo.com/?q=rust+async+traits");
+        let url = expand("ddg", template, &[]).unwrap();
+        assert_eq!(url, "https://duckduckgo.com/?q=");
+    }
+
+    #[test]
+    fn test_rest_after_positional() {
+        let template = "https://github.com/{0}/issues?q={*}";
+        let url = expand("issues", template, &["rust-lang/rust", "is:open", "ICE"]).unwrap();
+        assert_eq!(
+            url,
+            "https://github.com/rust-lang/rust/issues?q=is%3Aopen+ICE"
+        );
+    }
+
+    #[test]
+    fn test_encode_query() {
+        assert_eq!(encode_query("c++ a/b"), "c%2B%2B+a%2Fb");
+    }
+}
@@ -1,9 +1,14 @@
 use anyhow::{Context, Result};
-use std::fs;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Key, Table, Value};
 
+use crate::backup;
 use crate::config::{Config, DEFAULT_CONFIG};
-use crate::storage::Storage;
+use crate::diagnostic::{self, Diagnostic};
+use crate::doctor;
+use crate::storage::{sidecar, ConflictError, FileLock, Storage};
 
 const CONFIG_DIR: &str = ".config";
 const APP_NAME: &str = "dkdc";
@@ -12,11 +17,23 @@ const CONFIG_FILENAME: &str = "config.toml";
 
 pub struct TomlStorage {
     path: PathBuf,
+    /// How many backups of the file to keep.
+    backups: usize,
 }
 
 impl TomlStorage {
     pub fn new(path: PathBuf) -> Self {
-        Self { path }
+        Self {
+            path,
+            backups: backup::keep(),
+        }
+    }
+
+    /// Keep this many backups of the file instead of [`backup::keep`]; `0`
+    /// keeps none.
+    pub fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
     }
 
     /// Default config path: ~/.config/dkdc/links/config.toml
@@ -35,24 +52,160 @@ impl TomlStorage {
     pub fn with_default_path() -> Result<Self> {
         Ok(Self::new(Self::default_path()?))
     }
+
+    /// The file the config path points at. Writes go there, so a config
+    /// symlinked into a dotfiles repo stays a symlink.
+    fn real_path(&self) -> PathBuf {
+        if let Ok(path) = fs::canonicalize(&self.path) {
+            return path;
+        }
+        // A symlink to a file that doesn't exist yet
+        match fs::read_link(&self.path) {
+            Ok(target) => self.path.parent().unwrap_or(Path::new("")).join(target),
+            Err(_) => self.path.clone(),
+        }
+    }
+
+    fn read(&self) -> Result<Option<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).context("Failed to read config file"),
+        }
+    }
+
+    /// Write to a temp file and rename it over the config, so readers never
+    /// see a partially written file. The temp file takes the config's
+    /// permissions, and both it and the directory are synced so the rename
+    /// survives a crash.
+    fn write(&self, contents: &str) -> Result<()> {
+        let path = self.real_path();
+        let tmp_path = sidecar(&path, ".tmp");
+        let mut tmp = File::create(&tmp_path).context("Failed to write config file")?;
+        if let Ok(metadata) = fs::metadata(&path) {
+            tmp.set_permissions(metadata.permissions())
+                .context("Failed to set config file permissions")?;
+        }
+        tmp.write_all(contents.as_bytes())
+            .and_then(|()| tmp.sync_all())
+            .context("Failed to write config file")?;
+        fs::rename(&tmp_path, &path).context("Failed to replace config file")?;
+        sync_dir(&path)?;
+        Ok(())
+    }
+
+    /// Back up `existing` if it differs from `contents`, then write
+    /// `contents` in its place.
+    fn replace(&self, existing: Option<&str>, contents: &str) -> Result<()> {
+        if let Some(existing) = existing.filter(|e| *e != contents) {
+            backup::create(&self.path, existing, self.backups)?;
+        }
+        self.write(contents)
+    }
+
+    /// The config without reporting its problems, or `None` if the file
+    /// doesn't exist yet. For callers that check a combined config instead.
+    pub(crate) fn read_config(&self) -> Result<Option<Config>> {
+        self.read()?.map(|contents| parse(&contents)).transpose()
+    }
+}
+
+fn parse(contents: &str) -> Result<Config> {
+    toml::from_str(contents).context("Failed to parse config file")
+}
+
+/// Flush the directory entry for `path` after a rename.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<()> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty());
+    File::open(dir.unwrap_or(Path::new(".")))
+        .and_then(|dir| dir.sync_all())
+        .context("Failed to sync config directory")
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Render `config` as TOML. When there is an existing document, patch it so
+/// comments and ordering survive; otherwise build one from scratch. Either
+/// way links with metadata come out as inline tables.
+fn render(config: &Config, existing: Option<&str>) -> Result<String> {
+    let fresh: DocumentMut = toml::to_string(config)
+        .context("Failed to serialize config")?
+        .parse()
+        .context("Failed to serialize config")?;
+    let mut doc = existing
+        .and_then(|s| s.parse::<DocumentMut>().ok())
+        .unwrap_or_default();
+    patch_document(&mut doc, &fresh);
+    Ok(doc.to_string())
+}
+
+fn diagnose(config: &Config, contents: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = config.validate();
+    diagnostic::locate(&mut diagnostics, contents);
+    diagnostics
 }
 
 impl Storage for TomlStorage {
     fn load(&self) -> Result<Config> {
         let contents = fs::read_to_string(&self.path).context("Failed to read config file")?;
-        let config: Config = toml::from_str(&contents).context("Failed to parse config file")?;
+        let config = parse(&contents)?.loaded();
 
-        for warning in config.validate() {
-            eprintln!("[dkdc-links] warning: {warning}");
+        for diagnostic in diagnose(&config, &contents) {
+            eprintln!("{}", diagnostic.render(Some(&self.path), Some(&contents)));
         }
 
         Ok(config)
     }
 
+    fn stored(&self) -> Result<Config> {
+        parse(&self.read()?.context("Failed to read config file")?)
+    }
+
+    /// Locks the file the config path points at, so every path to the same
+    /// config shares one lock.
+    fn lock(&self) -> Result<Option<FileLock>> {
+        FileLock::acquire(sidecar(&self.real_path(), ".lock")).map(Some)
+    }
+
+    /// Diagnostics with spans pointing into the file.
+    fn diagnostics(&self) -> Result<Vec<Diagnostic>> {
+        let contents = fs::read_to_string(&self.path).context("Failed to read config file")?;
+        let config = parse(&contents)?;
+        let mut diagnostics = doctor::check(&config);
+        diagnostic::locate(&mut diagnostics, &contents);
+        Ok(diagnostics)
+    }
+
+    /// Fails with [`ConflictError`] if the links, aliases or groups in the
+    /// file changed since `config` was loaded from it. Edits that only touch
+    /// comments or formatting are kept.
     fn save(&self, config: &Config) -> Result<()> {
-        let contents = toml::to_string(config).context("Failed to serialize config")?;
-        fs::write(&self.path, contents).context("Failed to write config file")?;
-        Ok(())
+        let _lock = self.lock()?;
+        let existing = self.read()?;
+
+        let stored = existing.as_deref().and_then(|e| parse(e).ok());
+        if config.is_stale(stored.as_ref()) {
+            return Err(ConflictError {
+                path: self.path.clone(),
+            }
+            .into());
+        }
+
+        self.replace(existing.as_deref(), &render(config, existing.as_deref())?)
+    }
+
+    /// Holds the lock across the whole read-modify-write, so concurrent
+    /// updates from other processes are never lost.
+    fn update(&self, f: &mut dyn FnMut(&mut Config) -> Result<()>) -> Result<()> {
+        let _lock = self.lock()?;
+        let existing = self.read()?.context("Failed to read config file")?;
+        let mut config = parse(&existing)?;
+        f(&mut config)?;
+        self.replace(Some(&existing), &render(&config, Some(&existing))?)
     }
 
     fn init(&self) -> Result<()> {
@@ -62,7 +215,11 @@ impl Storage for TomlStorage {
                 .parent()
                 .context("Invalid config path: no parent directory")?;
             fs::create_dir_all(config_dir).context("Failed to create config directory")?;
-            fs::write(&self.path, DEFAULT_CONFIG).context("Failed to write default config")?;
+            let _lock = self.lock()?;
+            if self.read()?.is_none() {
+                self.write(DEFAULT_CONFIG)
+                    .context("Failed to write default config")?;
+            }
         }
         Ok(())
     }
@@ -76,9 +233,108 @@ impl Storage for TomlStorage {
     }
 }
 
+/// Update `doc` in place to hold the same data as `fresh`, touching only the
+/// entries that changed. Unknown top-level keys in `doc` are left alone.
+fn patch_document(doc: &mut DocumentMut, fresh: &DocumentMut) {
+    for (section, item) in fresh.iter() {
+        let Some(fresh_table) = item.as_table() else {
+            continue;
+        };
+        match doc.get_mut(section).and_then(Item::as_table_mut) {
+            Some(table) => patch_table(table, fresh_table),
+            None if fresh_table.is_empty() => {}
+            None => {
+                let mut table = Table::new();
+                patch_table(&mut table, fresh_table);
+                doc.insert(section, Item::Table(table));
+            }
+        }
+    }
+}
+
+fn patch_table(table: &mut Table, fresh: &Table) {
+    let fresh_values: Vec<(&str, Value)> = fresh
+        .iter()
+        .filter_map(|(k, item)| Some((k, item.clone().into_value().ok()?)))
+        .collect();
+
+    // New keys, in order; a removed key whose value reappears under one of
+    // these names is treated as a rename and keeps its position and comments.
+    let mut added: Vec<&(&str, Value)> = fresh_values
+        .iter()
+        .filter(|(k, _)| !table.contains_key(k))
+        .collect();
+
+    let mut patched: Vec<(Key, Item)> = Vec::with_capacity(fresh_values.len());
+    for (key, item) in table.iter() {
+        let key = table.key(key).expect("key from iter").clone();
+        let old = item.clone().into_value().ok();
+
+        if let Some((_, new)) = fresh_values.iter().find(|(k, _)| *k == key.get()) {
+            patched.push((key, Item::Value(keep_decor(old.as_ref(), new))));
+        } else if let Some(pos) = added
+            .iter()
+            .position(|(_, new)| old.as_ref().is_some_and(|old| same_value(old, new)))
+        {
+            let (name, new) = added.remove(pos);
+            let renamed = Key::new(*name).with_leaf_decor(key.leaf_decor().clone());
+            patched.push((renamed, Item::Value(keep_decor(old.as_ref(), new))));
+        }
+    }
+    // Place each new key after the key that precedes it in `fresh`
+    for (name, new) in added {
+        let preceding = fresh_values
+            .iter()
+            .take_while(|(k, _)| k != name)
+            .filter_map(|(k, _)| patched.iter().position(|(key, _)| key.get() == *k))
+            .max();
+        let index = preceding.map_or(0, |i| i + 1);
+        patched.insert(index, (Key::new(*name), Item::Value(new.clone())));
+    }
+
+    table.clear();
+    for (key, item) in patched {
+        table.insert_formatted(&key, item);
+    }
+}
+
+/// Keep the old value verbatim if it is unchanged, otherwise swap in the new
+/// value while keeping the old value's surrounding whitespace and comments.
+fn keep_decor(old: Option<&Value>, new: &Value) -> Value {
+    match old {
+        Some(old) if same_value(old, new) => old.clone(),
+        Some(old) => {
+            let mut new = new.clone();
+            *new.decor_mut() = old.decor().clone();
+            new
+        }
+        None => new.clone(),
+    }
+}
+
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Float(a), Value::Float(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| same_value(a, b))
+        }
+        (Value::InlineTable(a), Value::InlineTable(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).is_some_and(|w| same_value(v, w)))
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::Link;
     use std::io::Write;
 
     #[test]
@@ -145,10 +401,414 @@ dev = ["gh"]
         storage.init().unwrap();
 
         let config = storage.load().unwrap();
+        assert_eq!(config.links["rust"].url, "https://rust-lang.org");
+    }
+
+    #[test]
+    fn test_crud_defaults_roundtrip_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = TomlStorage::new(dir.path().join("config.toml"));
+        storage.save(&Config::default()).unwrap();
+
+        storage.put_link("github", "https://github.com").unwrap();
+        storage.put_alias("gh", "github").unwrap();
+        storage.put_group("dev", &["gh".to_string()]).unwrap();
         assert_eq!(
-            config.links.get("rust"),
-            Some(&"https://rust-lang.org".to_string())
+            storage
+                .get_link("github")
+                .unwrap()
+                .map(|l| l.url)
+                .as_deref(),
+            Some("https://github.com")
+        );
+
+        storage.rename_link("github", "hub").unwrap();
+        assert_eq!(storage.get_alias("gh").unwrap().as_deref(), Some("hub"));
+
+        storage.delete_group("dev").unwrap();
+        assert!(storage.get_group("dev").unwrap().is_none());
+        assert!(storage.delete_group("dev").is_err());
+    }
+
+    #[test]
+    fn test_failed_update_does_not_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = TomlStorage::new(dir.path().join("config.toml"));
+        storage.save(&Config::default()).unwrap();
+
+        assert!(storage.put_alias("gh", "missing").is_err());
+        assert!(storage.load().unwrap().aliases.is_empty());
+    }
+
+    const COMMENTED: &str = r#"# my bookmarks
+
+[links]
+# work stuff
+github = "https://github.com" # the big one
+rust = "https://rust-lang.org"
+
+[aliases]
+gh = "github"
+
+[groups]
+dev = ["gh", "rust"]
+"#;
+
+    #[test]
+    fn test_save_unchanged_is_byte_identical() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        let config = storage.load().unwrap();
+        storage.save(&config).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), COMMENTED);
+    }
+
+    #[test]
+    fn test_save_preserves_comments_on_edit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        let mut config = storage.load().unwrap();
+        config.links["github"].url = "https://github.com/rust-lang".to_string();
+        storage.save(&config).unwrap();
+
+        let expected = COMMENTED.replace(
+            r#"github = "https://github.com" # the big one"#,
+            r#"github = "https://github.com/rust-lang" # the big one"#,
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_diagnostics_have_spans() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            COMMENTED.replace("gh = \"github\"", "gh = \"githb\""),
+        )
+        .unwrap();
+
+        let storage = TomlStorage::new(path);
+        let diagnostics = storage.diagnostics().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        let span = diagnostics[0].span.expect("span");
+        assert_eq!((span.line, span.column), (9, 6));
+        assert_eq!(
+            diagnostics[0].suggestion.as_deref(),
+            Some("did you mean 'github'?")
+        );
+
+        // github is now unused; doctor notes it at its own line
+        assert_eq!(diagnostics[1].key, "github");
+        assert_eq!(diagnostics[1].span.expect("span").line, 5);
+    }
+
+    #[test]
+    fn test_put_link_writes_metadata_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        storage
+            .update(&mut |config| {
+                let mut link = config.links["github"].clone();
+                link.description = Some("code hosting".to_string());
+                link.tags = vec!["dev".to_string()];
+                config.put_link("github", link)
+            })
+            .unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let line = contents.lines().find(|l| l.starts_with("github")).unwrap();
+        assert!(line.starts_with(
+            r#"github = { url = "https://github.com", description = "code hosting", tags = ["dev"], updated = "#
+        ));
+        assert!(line.ends_with("} # the big one"));
+
+        let link = &storage.load().unwrap().links["github"];
+        assert_eq!(link.description.as_deref(), Some("code hosting"));
+        assert!(link.updated.is_some());
+    }
+
+    #[test]
+    fn test_rename_changes_only_lines_naming_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        storage.rename_link("rust", "rust-lang").unwrap();
+
+        let expected = COMMENTED
+            .replace("rust = ", "rust-lang = ")
+            .replace("\"rust\"]", "\"rust-lang\"]");
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_save_does_not_add_empty_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let contents = "[links]\nrust = \"https://rust-lang.org\"\n";
+        fs::write(&path, contents).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        storage.save(&storage.load().unwrap()).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_new_entries_append_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        let mut config = storage.load().unwrap();
+        for name in ["zzz", "aaa", "mmm"] {
+            config
+                .links
+                .insert(name.to_string(), Link::new("https://example.com"));
+        }
+        storage.save(&config).unwrap();
+
+        let expected = COMMENTED.replace(
+            "rust = \"https://rust-lang.org\"\n",
+            "rust = \"https://rust-lang.org\"\n\
+             zzz = \"https://example.com\"\n\
+             aaa = \"https://example.com\"\n\
+             mmm = \"https://example.com\"\n",
         );
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_save_adds_and_removes_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        storage.delete_alias("gh").unwrap();
+        storage.put_link("crates", "https://crates.io").unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# my bookmarks\n"));
+        assert!(contents.contains("# the big one"));
+        assert!(!contents.contains("gh = "));
+        assert!(contents.contains("crates = \"https://crates.io\"\n"));
+
+        let config = storage.load().unwrap();
+        assert!(config.aliases.is_empty());
+        assert_eq!(config.links.len(), 3);
+    }
+
+    #[test]
+    fn test_save_detects_external_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        let mut config = storage.load().unwrap();
+
+        let edited = COMMENTED.replace("rust-lang.org", "rust-lang.org/learn");
+        fs::write(&path, &edited).unwrap();
+
+        config.add_link("crates", "https://crates.io").unwrap();
+        let err = storage.save(&config).unwrap_err();
+        assert!(err.downcast_ref::<ConflictError>().is_some());
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+    }
+
+    #[test]
+    fn test_save_detects_changes_through_the_same_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        let mut stale = storage.load().unwrap();
+        storage.put_link("crates", "https://crates.io").unwrap();
+        storage.load().unwrap();
+
+        stale.add_link("docs", "https://docs.rs").unwrap();
+        let err = storage.save(&stale).unwrap_err();
+        assert!(err.downcast_ref::<ConflictError>().is_some());
+        assert!(storage.load().unwrap().links.contains_key("crates"));
+    }
+
+    #[test]
+    fn test_save_keeps_comment_only_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        let mut config = storage.load().unwrap();
+        fs::write(&path, format!("# edited in vim\n{COMMENTED}")).unwrap();
+
+        config.add_link("crates", "https://crates.io").unwrap();
+        storage.save(&config).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("# edited in vim\n"));
+        assert!(contents.contains("crates = "));
+    }
+
+    #[test]
+    fn test_update_keeps_external_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, COMMENTED).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        storage.load().unwrap();
+        fs::write(
+            &path,
+            COMMENTED.replace("rust-lang.org", "rust-lang.org/learn"),
+        )
+        .unwrap();
+
+        storage.put_link("crates", "https://crates.io").unwrap();
+        let config = storage.load().unwrap();
+        assert_eq!(config.links["rust"].url, "https://rust-lang.org/learn");
+        assert!(config.links.contains_key("crates"));
+    }
+
+    #[test]
+    fn test_save_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let storage = TomlStorage::new(path.clone());
+        storage.init().unwrap();
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+
+        let mut names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["config.toml", "config.toml.backups"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_symlink_and_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let dotfiles = dir.path().join("dotfiles");
+        fs::create_dir(&dotfiles).unwrap();
+        let real = dotfiles.join("links.toml");
+        fs::write(&real, COMMENTED).unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o600)).unwrap();
+        let path = dir.path().join("config.toml");
+        symlink(&real, &path).unwrap();
+
+        let storage = TomlStorage::new(path.clone());
+        storage.put_link("crates", "https://crates.io").unwrap();
+
+        assert!(fs::symlink_metadata(&path).unwrap().is_symlink());
+        assert!(fs::read_to_string(&real).unwrap().contains("crates = "));
+        let mode = fs::metadata(&real).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!sidecar(&real, ".tmp").exists());
+        assert!(!sidecar(&real, ".lock").exists());
+    }
+
+    #[test]
+    fn test_concurrent_updates_are_not_lost() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        TomlStorage::new(path.clone()).init().unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let storage = TomlStorage::new(path);
+                    storage
+                        .put_link(&format!("link-{i}"), "https://example.com")
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let config = TomlStorage::new(path).load().unwrap();
+        assert!((0..8).all(|i| config.links.contains_key(&format!("link-{i}"))));
+    }
+
+    #[test]
+    fn test_saves_are_backed_up_and_restorable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let storage = TomlStorage::new(path.clone());
+        storage.init().unwrap();
+        let original = fs::read_to_string(&path).unwrap();
+
+        storage.put_link("rust", "https://rust-lang.org").unwrap();
+        let backups = backup::list(&path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].read().unwrap(), original);
+
+        // Saving without changes takes no backup
+        storage.save(&storage.load().unwrap()).unwrap();
+        assert_eq!(backup::list(&path).unwrap().len(), 1);
+
+        backup::restore(&storage, &backups[0]).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(!storage.load().unwrap().links.contains_key("rust"));
+    }
+
+    #[test]
+    fn test_backup_count_is_configurable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let storage = TomlStorage::new(path.clone()).with_backups(3);
+        storage.init().unwrap();
+        for i in 0..5 {
+            storage
+                .put_link(&format!("link-{i}"), "https://example.com")
+                .unwrap();
+        }
+        let backups = backup::list(&path).unwrap();
+        assert_eq!(backups.len(), 3);
+        assert!(backups[2].read().unwrap().contains("link-3"));
+
+        let storage = TomlStorage::new(path.clone()).with_backups(0);
+        storage.put_link("link-5", "https://example.com").unwrap();
+        assert_eq!(backup::list(&path).unwrap(), backups);
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let storage = TomlStorage::new(path.clone());
+        storage.init().unwrap();
+
+        fs::create_dir_all(backup::dir(&path)).unwrap();
+        fs::write(
+            backup::dir(&path).join("20261017-000000-000.toml"),
+            "[links",
+        )
+        .unwrap();
+        let backup = backup::find(&path, "20261017").unwrap();
+        let err = backup::restore(&storage, &backup).unwrap_err().to_string();
+        assert!(err.contains("is not a valid config"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), DEFAULT_CONFIG);
     }
 
     #[test]
@@ -5,23 +5,25 @@ use axum::response::Html;
 use axum::routing::{get, post};
 use axum::Router;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
 
-use crate::config::Config;
+use crate::config::{parse_entries, Config, DeleteMode, Link};
+use crate::diagnostic::Diagnostic;
+use crate::journal::JournaledStorage;
 use crate::storage::Storage;
 use crate::strings;
 
 struct AppState {
-    storage: Mutex<Box<dyn Storage>>,
+    storage: Mutex<JournaledStorage>,
 }
 
 impl AppState {
-    fn load_config(&self) -> Config {
-        self.storage.lock().unwrap().load().unwrap_or_default()
+    fn storage(&self) -> MutexGuard<'_, JournaledStorage> {
+        self.storage.lock().unwrap()
     }
 
-    fn save_config(&self, config: &Config) {
-        let _ = self.storage.lock().unwrap().save(config);
+    fn load_config(&self) -> Config {
+        self.storage().load().unwrap_or_default()
     }
 }
 
@@ -101,7 +103,11 @@ fn page(body: &str) -> String {
     .copy-btn.copied {{ color: #4ade80; }}
     td.url {{ }}
     td.url .url-cell {{ display: flex; align-items: center; gap: 6px; }}
+    .link-meta {{ color: #8c8ca6; font-size: 0.75rem; margin-top: 2px; }}
+    .tag {{ color: #22d3ee; background: #242438; border: 1px solid #2e2e47; border-radius: 3px; padding: 0 4px; font-size: 0.7rem; }}
     td.target .target-cell {{ display: flex; align-items: center; gap: 6px; }}
+    tr.invalid td {{ background: #2a1a24; }}
+    tr.invalid td.name {{ box-shadow: inset 2px 0 0 #ff7373; }}
     .error-banner {{ background: #3a1a2a; border: 1px solid #5c2a2a; color: #ff7373; padding: 8px 12px; border-radius: 6px; margin-bottom: 12px; font-size: 0.8rem; cursor: pointer; }}
     .editable {{ cursor: pointer; }}
     .editable:hover {{ background: #2e2e47; border-radius: 3px; }}
@@ -128,6 +134,10 @@ fn page(body: &str) -> String {
     .modal .btn-cancel:hover {{ border-color: #666680; color: #edeedf; }}
     .modal .btn-confirm {{ background: #3a1a2a; border-color: #ff7373; color: #ff7373; padding: 6px 16px; font-size: 0.8rem; }}
     .modal .btn-confirm:hover {{ background: #4a2030; border-color: #ffa0a0; color: #ffa0a0; }}
+    .modal ul {{ color: #edeedf; font-size: 0.8rem; margin: -8px 0 16px 20px; }}
+    /* undo toast */
+    .toast {{ position: fixed; bottom: 24px; left: 50%; transform: translateX(-50%); background: #141421; border: 1px solid #2e2e47; border-radius: 6px; padding: 8px 12px; color: #8c8ca6; font-size: 0.8rem; display: flex; gap: 12px; align-items: center; z-index: 50; }}
+    .toast .dismiss {{ cursor: pointer; opacity: 0.6; }}
     @media (max-width: 680px) {{ body {{ width: auto; padding: 24px 16px; }} }}
   </style>
 </head>
@@ -143,8 +153,13 @@ fn page(body: &str) -> String {
     <div class="modal">
       <h3 id="confirm-title">confirm delete</h3>
       <p id="confirm-message"></p>
+      <div id="confirm-affected">
+        <p>still referred to by:</p>
+        <ul id="confirm-affected-list"></ul>
+      </div>
       <div class="modal-actions">
         <button class="btn btn-cancel" onclick="closeModal()">cancel</button>
+        <button class="btn btn-confirm" id="confirm-orphan">leave references</button>
         <button class="btn btn-confirm" id="confirm-btn">delete</button>
       </div>
     </div>
@@ -153,18 +168,40 @@ fn page(body: &str) -> String {
   <script>
     // -- confirm modal ---
     var pendingAction = null;
-    function confirmDelete(title, message, action) {{
+    // affected: aliases and groups outside the deletion that refer to it;
+    // action is called with the delete mode
+    function confirmDelete(title, message, affected, action) {{
       document.getElementById('confirm-title').textContent = title;
       document.getElementById('confirm-message').textContent = message;
+      var list = document.getElementById('confirm-affected-list');
+      list.innerHTML = '';
+      affected.forEach(function(label) {{
+        var li = document.createElement('li');
+        li.textContent = label;
+        list.appendChild(li);
+      }});
+      document.getElementById('confirm-affected').style.display = affected.length ? '' : 'none';
       document.getElementById('confirm-modal').classList.add('visible');
       pendingAction = action;
-      // wire up confirm button
+      // wire up confirm buttons; with nothing affected a cascade only
+      // touches what is being deleted
+      function confirmWith(mode) {{
+        return function() {{
+          var action = pendingAction;
+          closeModal();
+          if (action) action(mode);
+        }};
+      }}
       var btn = document.getElementById('confirm-btn');
-      btn.onclick = function() {{
-        var action = pendingAction;
-        closeModal();
-        if (action) action();
-      }};
+      btn.textContent = affected.length ? 'remove references' : 'delete';
+      btn.onclick = confirmWith('cascade');
+      var orphan = document.getElementById('confirm-orphan');
+      orphan.style.display = affected.length ? '' : 'none';
+      orphan.onclick = confirmWith('orphan');
+    }}
+    function rowAffected(checkbox) {{
+      var affected = checkbox.dataset.affected;
+      return affected ? affected.split('\n') : [];
     }}
     function closeModal() {{
       document.getElementById('confirm-modal').classList.remove('visible');
@@ -236,12 +273,13 @@ fn page(body: &str) -> String {
     }}
 
     // -- single delete via modal ---
-    function deleteSingle(type, name) {{
+    function deleteSingle(type, name, btn) {{
       confirmDelete(
         'delete ' + type,
-        'are you sure you want to delete ' + type + ' "' + name + '"? this cannot be undone.',
-        function() {{
-          htmx.ajax('POST', '/delete/' + type + '/' + encodeURIComponent(name), {{target: '#content', swap: 'innerHTML'}});
+        'are you sure you want to delete ' + type + ' "' + name + '"? you can undo this afterwards.',
+        rowAffected(btn.closest('tr').querySelector('input.row-check')),
+        function(mode) {{
+          htmx.ajax('POST', '/delete/' + type + '/' + encodeURIComponent(name) + '?mode=' + mode, {{target: '#content', swap: 'innerHTML'}});
         }}
       );
     }}
@@ -271,27 +309,25 @@ fn page(body: &str) -> String {
       if (checked.length === 0) return;
       var items = [];
       checked.forEach(function(cb) {{ items.push(cb.dataset.type + ' "' + cb.dataset.name + '"'); }});
+      // what the selection leaves dangling, not counting selected items
+      var selected = [];
+      checked.forEach(function(cb) {{ selected.push(cb.dataset.type + " '" + cb.dataset.name + "'"); }});
+      var affected = [];
+      checked.forEach(function(cb) {{
+        rowAffected(cb).forEach(function(label) {{
+          if (selected.indexOf(label) < 0 && affected.indexOf(label) < 0) affected.push(label);
+        }});
+      }});
       var count = checked.length;
       confirmDelete(
         'delete ' + count + ' item' + (count > 1 ? 's' : ''),
-        'are you sure you want to delete: ' + items.join(', ') + '? this cannot be undone.',
-        function() {{
-          // collect names grouped by type
-          var toDelete = [];
-          checked.forEach(function(cb) {{
-            toDelete.push({{type: cb.dataset.type, name: cb.dataset.name}});
-          }});
-          // delete sequentially, refresh at end
-          var i = 0;
-          function next() {{
-            if (i >= toDelete.length) {{
-              htmx.ajax('GET', '/content', {{target: '#content', swap: 'innerHTML'}});
-              return;
-            }}
-            var item = toDelete[i++];
-            fetch('/delete/' + item.type + '/' + encodeURIComponent(item.name), {{method: 'POST'}}).then(next);
-          }}
-          next();
+        'are you sure you want to delete: ' + items.join(', ') + '? you can undo this afterwards.',
+        affected,
+        function(mode) {{
+          // one request, so a single undo brings everything back
+          var names = [];
+          checked.forEach(function(cb) {{ names.push(cb.dataset.name); }});
+          htmx.ajax('POST', '/delete?mode=' + mode, {{target: '#content', swap: 'innerHTML', values: {{names: names.join('\n')}}}});
         }}
       );
     }}
@@ -315,6 +351,16 @@ fn page(body: &str) -> String {
       filterRows();
     }}
 
+    // -- undo / redo ---
+    document.addEventListener('keydown', function(e) {{
+      if (!(e.ctrlKey || e.metaKey) || e.target.closest('input')) return;
+      var key = e.key.toLowerCase();
+      var route = key === 'y' || (key === 'z' && e.shiftKey) ? '/redo' : key === 'z' ? '/undo' : null;
+      if (!route) return;
+      e.preventDefault();
+      htmx.ajax('POST', route, {{target: '#content', swap: 'innerHTML'}});
+    }});
+
     // re-bind checkboxes after htmx swaps
     document.body.addEventListener('htmx:afterSwap', function() {{
       updateBulkBar();
@@ -330,9 +376,9 @@ fn page(body: &str) -> String {
 /// Resolve a name to a URL: check aliases first, then direct links.
 fn resolve_url<'a>(name: &str, config: &'a Config) -> Option<&'a str> {
     if let Some(target) = config.aliases.get(name) {
-        config.links.get(target).map(String::as_str)
+        config.links.get(target).map(|l| l.url.as_str())
     } else {
-        config.links.get(name).map(String::as_str)
+        config.links.get(name).map(|l| l.url.as_str())
     }
 }
 
@@ -349,26 +395,77 @@ fn copy_btn(url: &str) -> String {
     )
 }
 
-fn link_row(name: &str, url: &str) -> String {
+fn link_meta(link: &Link) -> String {
+    if link.description.is_none() && link.tags.is_empty() {
+        return String::new();
+    }
+    let description = link.description.as_deref().map(escape).unwrap_or_default();
+    let tags: String = link
+        .tags
+        .iter()
+        .map(|t| format!(r##" <span class="tag">{}</span>"##, escape(t)))
+        .collect();
+    format!(r##"<div class="link-meta">{description}{tags}</div>"##)
+}
+
+/// Attributes that flag a row with problems, listing them in its tooltip.
+fn issue_attrs(diagnostics: &[Diagnostic], section: &str, key: &str) -> String {
+    let messages: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.is_for(section, key))
+        .map(ToString::to_string)
+        .collect();
+    if messages.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r##" class="invalid" title="{}""##,
+            escape(&messages.join("\n"))
+        )
+    }
+}
+
+/// The aliases and groups that refer to `name`, for the delete dialog.
+fn affected_attr(config: &Config, name: &str) -> String {
+    let labels: Vec<String> = config
+        .dependents(name)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!(r##" data-affected="{}""##, escape(&labels.join("\n")))
+    }
+}
+
+fn link_row(name: &str, link: &Link, config: &Config, diagnostics: &[Diagnostic]) -> String {
     let n = escape(name);
+    let url = link.url.as_str();
     let u = escape(url);
+    let f = escape(&link.filter_text());
     let name_link = linked_name(name, url);
     let copy = copy_btn(url);
+    let meta = link_meta(link);
+    let issue = issue_attrs(diagnostics, "links", name);
+    let affected = affected_attr(config, name);
     format!(
-        r##"<tr data-filter="{n} {u}">
-  <td class="check"><input type="checkbox" class="row-check" data-type="link" data-name="{n}" onchange="updateBulkBar()"></td>
+        r##"<tr{issue} data-filter="{n} {f}">
+  <td class="check"><input type="checkbox" class="row-check" data-type="link" data-name="{n}"{affected} onchange="updateBulkBar()"></td>
   <td class="name editable" ondblclick="startEdit('link','{n}','name','{n}')">{name_link}</td>
-  <td class="url editable" ondblclick="startEdit('link','{n}','url','{u}')"><span class="url-cell">{copy}<a href="{u}" target="_blank" rel="noopener">{u}</a></span></td>
+  <td class="url editable" ondblclick="startEdit('link','{n}','url','{u}')"><span class="url-cell">{copy}<a href="{u}" target="_blank" rel="noopener">{u}</a></span>{meta}</td>
   <td class="actions">
-    <button class="btn btn-danger" onclick="deleteSingle('link','{n}')">delete</button>
+    <button class="btn btn-danger" onclick="deleteSingle('link','{n}',this)">delete</button>
   </td>
 </tr>"##
     )
 }
 
-fn alias_row(alias: &str, target: &str, config: &Config) -> String {
+fn alias_row(alias: &str, target: &str, config: &Config, diagnostics: &[Diagnostic]) -> String {
     let a = escape(alias);
     let t = escape(target);
+    let issue = issue_attrs(diagnostics, "aliases", alias);
+    let affected = affected_attr(config, alias);
     let resolved = resolve_url(alias, config);
     let name_cell = if let Some(url) = resolved {
         format!(
@@ -382,29 +479,35 @@ fn alias_row(alias: &str, target: &str, config: &Config) -> String {
         .as_ref()
         .map(|url| copy_btn(url))
         .unwrap_or_default();
-    let target_cell = if let Some(url) = config.links.get(target) {
-        let u = escape(url);
+    let target_cell = if let Some(link) = config.links.get(target) {
+        let u = escape(&link.url);
         format!(r##"<a href="{u}" target="_blank" rel="noopener" title="{u}">{t}</a>"##)
     } else {
         t.clone()
     };
     format!(
-        r##"<tr data-filter="{a} {t}">
-  <td class="check"><input type="checkbox" class="row-check" data-type="alias" data-name="{a}" onchange="updateBulkBar()"></td>
+        r##"<tr{issue} data-filter="{a} {t}">
+  <td class="check"><input type="checkbox" class="row-check" data-type="alias" data-name="{a}"{affected} onchange="updateBulkBar()"></td>
   <td class="name editable" ondblclick="startEdit('alias','{a}','alias','{a}')">{name_cell}</td>
   <td class="target editable" ondblclick="startEdit('alias','{a}','target','{t}')"><span class="target-cell">{copy_cell}{target_cell}</span></td>
   <td class="actions">
-    <button class="btn btn-danger" onclick="deleteSingle('alias','{a}')">delete</button>
+    <button class="btn btn-danger" onclick="deleteSingle('alias','{a}',this)">delete</button>
   </td>
 </tr>"##
     )
 }
 
-fn group_row(name: &str, entries: &[String], config: &Config) -> String {
+fn group_row(
+    name: &str,
+    entries: &[String],
+    config: &Config,
+    diagnostics: &[Diagnostic],
+) -> String {
     let n = escape(name);
     // Collect resolved URLs for the "open all" action
-    let urls: Vec<String> = entries
-        .iter()
+    let urls: Vec<String> = config
+        .group_members(name)
+        .into_iter()
         .filter_map(|entry| resolve_url(entry, config).map(escape))
         .collect();
     let urls_json: Vec<String> = urls.iter().map(|u| format!("'{u}'")).collect();
@@ -433,13 +536,15 @@ fn group_row(name: &str, entries: &[String], config: &Config) -> String {
         )
     };
     let entries_raw = entries.join(", ");
+    let issue = issue_attrs(diagnostics, "groups", name);
+    let affected = affected_attr(config, name);
     format!(
-        r##"<tr data-filter="{n} {filter_str}">
-  <td class="check"><input type="checkbox" class="row-check" data-type="group" data-name="{n}" onchange="updateBulkBar()"></td>
+        r##"<tr{issue} data-filter="{n} {filter_str}">
+  <td class="check"><input type="checkbox" class="row-check" data-type="group" data-name="{n}"{affected} onchange="updateBulkBar()"></td>
   <td class="name editable" ondblclick="startEdit('group','{n}','name','{n}')">{name_cell}</td>
   <td class="entries editable" ondblclick="startEdit('group','{n}','entries','{entries_raw}')">{entries_html}</td>
   <td class="actions">
-    <button class="btn btn-danger" onclick="deleteSingle('group','{n}')">delete</button>
+    <button class="btn btn-danger" onclick="deleteSingle('group','{n}',this)">delete</button>
   </td>
 </tr>"##
     )
@@ -455,6 +560,7 @@ fn render_content(config: &Config, sort: SortField, error: Option<&str>) -> Stri
     let mut links: Vec<_> = config.links.iter().collect();
     let mut aliases: Vec<_> = config.aliases.iter().collect();
     let mut groups: Vec<_> = config.groups.iter().collect();
+    let diagnostics = config.validate();
 
     match sort {
         SortField::Name => {
@@ -463,7 +569,7 @@ fn render_content(config: &Config, sort: SortField, error: Option<&str>) -> Stri
             groups.sort_by_key(|(k, _)| k.as_str());
         }
         SortField::Url => {
-            links.sort_by_key(|(_, v)| v.as_str());
+            links.sort_by_key(|(_, v)| v.url.as_str());
             aliases.sort_by_key(|(_, v)| v.as_str());
             groups.sort_by_key(|(k, _)| k.as_str());
         }
@@ -551,8 +657,8 @@ fn render_content(config: &Config, sort: SortField, error: Option<&str>) -> Stri
         html.push_str(&format!(
             r##"<table><colgroup><col class="col-check"><col class="col-name"><col class="col-value"><col class="col-actions"></colgroup><tr><th class="check"><input type="checkbox" class="select-all" onchange="toggleAll(this)"></th><th class="sortable{name_cls}" hx-get="/content?sort=name" hx-target="#content">name</th><th class="sortable{url_cls}" hx-get="/content?sort=url" hx-target="#content">url</th><th></th></tr>"##,
         ));
-        for (name, url) in &links {
-            html.push_str(&link_row(name, url));
+        for (name, link) in &links {
+            html.push_str(&link_row(name, link, config, &diagnostics));
         }
         html.push_str("</table>");
     }
@@ -567,7 +673,7 @@ fn render_content(config: &Config, sort: SortField, error: Option<&str>) -> Stri
             r##"<table><colgroup><col class="col-check"><col class="col-name"><col class="col-value"><col class="col-actions"></colgroup><tr><th class="check"><input type="checkbox" class="select-all" onchange="toggleAll(this)"></th><th class="sortable{name_cls}" hx-get="/content?sort=name" hx-target="#content">alias</th><th class="sortable{url_cls}" hx-get="/content?sort=url" hx-target="#content">target</th><th></th></tr>"##,
         ));
         for (alias, target) in &aliases {
-            html.push_str(&alias_row(alias, target, config));
+            html.push_str(&alias_row(alias, target, config, &diagnostics));
         }
         html.push_str("</table>");
     }
@@ -582,7 +688,7 @@ fn render_content(config: &Config, sort: SortField, error: Option<&str>) -> Stri
             r##"<table><colgroup><col class="col-check"><col class="col-name"><col class="col-value"><col class="col-actions"></colgroup><tr><th class="check"><input type="checkbox" class="select-all" onchange="toggleAll(this)"></th><th class="sortable{name_cls}" hx-get="/content?sort=name" hx-target="#content">group</th><th>entries</th><th></th></tr>"##,
         ));
         for (name, entries) in &groups {
-            html.push_str(&group_row(name, entries, config));
+            html.push_str(&group_row(name, entries, config, &diagnostics));
         }
         html.push_str("</table>");
     }
@@ -633,81 +739,160 @@ fn content_err(state: &Arc<AppState>, msg: &str) -> Html<String> {
     ))
 }
 
+/// Render fresh content, surfacing a failed change as an error banner and
+/// one that changed something as a toast offering to undo it.
+fn content_result(state: &Arc<AppState>, result: anyhow::Result<Option<String>>) -> Html<String> {
+    match result {
+        Ok(change) => {
+            let Html(mut html) = content_ok(state);
+            if let Some(summary) = change {
+                html.push_str(&toast(&summary, "undo"));
+            }
+            Html(html)
+        }
+        Err(e) => content_err(state, &e.to_string()),
+    }
+}
+
+/// A message with a button that posts to `/{action}`.
+fn toast(message: &str, action: &str) -> String {
+    let m = escape(message);
+    format!(
+        r##"<div class="toast" id="toast"><span>{m}</span><button class="btn" hx-post="/{action}" hx-target="#content">{action}</button><span class="dismiss" onclick="this.parentNode.remove()">✕</span></div>"##
+    )
+}
+
 async fn add_link(State(state): S, axum::extract::Form(form): Form) -> Html<String> {
     let name = form.get("name").cloned().unwrap_or_default();
     let url = form.get("url").cloned().unwrap_or_default();
-    if !name.is_empty() && !url.is_empty() {
-        let mut config = state.load_config();
-        config.links.insert(name, url);
-        state.save_config(&config);
+    if name.is_empty() || url.is_empty() {
+        return content_ok(&state);
     }
-    content_ok(&state)
+    let result = state
+        .storage()
+        .change(&mut |config| config.add_link(&name, &url));
+    content_result(&state, result)
 }
 
 async fn add_alias(State(state): S, axum::extract::Form(form): Form) -> Html<String> {
     let alias = form.get("alias").cloned().unwrap_or_default();
     let target = form.get("target").cloned().unwrap_or_default();
-    if !alias.is_empty() && !target.is_empty() {
-        let config = state.load_config();
-        if !config.links.contains_key(&target) {
-            return content_err(&state, &strings::err_alias_target_missing(&target));
-        }
-        let mut config = config;
-        config.aliases.insert(alias, target);
-        state.save_config(&config);
+    if alias.is_empty() || target.is_empty() {
+        return content_ok(&state);
     }
-    content_ok(&state)
+    let result = state
+        .storage()
+        .change(&mut |config| config.add_alias(&alias, &target));
+    content_result(&state, result)
 }
 
 async fn add_group(State(state): S, axum::extract::Form(form): Form) -> Html<String> {
     let name = form.get("name").cloned().unwrap_or_default();
-    let entries_raw = form.get("entries").cloned().unwrap_or_default();
-    if !name.is_empty() && !entries_raw.is_empty() {
-        let entries: Vec<String> = entries_raw
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-        if !entries.is_empty() {
-            let config = state.load_config();
-            let missing: Vec<&str> = entries
-                .iter()
-                .filter(|e| {
-                    !config.links.contains_key(e.as_str())
-                        && !config.aliases.contains_key(e.as_str())
-                })
-                .map(String::as_str)
-                .collect();
-            if !missing.is_empty() {
-                return content_err(&state, &strings::err_group_entries_missing(&missing));
+    let entries = parse_entries(form.get("entries").map(String::as_str).unwrap_or_default());
+    if name.is_empty() || entries.is_empty() {
+        return content_ok(&state);
+    }
+    let result = state
+        .storage()
+        .change(&mut |config| config.add_group(&name, entries.clone()));
+    content_result(&state, result)
+}
+
+#[derive(Debug, serde::Deserialize, Default)]
+struct DeleteQuery {
+    #[serde(default)]
+    mode: Option<String>,
+}
+
+fn parse_delete_mode(q: &DeleteQuery) -> DeleteMode {
+    match q.mode.as_deref() {
+        Some("cascade") => DeleteMode::Cascade,
+        Some("orphan") => DeleteMode::Orphan,
+        _ => DeleteMode::Refuse,
+    }
+}
+
+/// Names share one namespace, so the three delete routes behave the same.
+fn delete_name(state: &Arc<AppState>, name: &str, q: &DeleteQuery) -> Html<String> {
+    let mode = parse_delete_mode(q);
+    let result = state
+        .storage()
+        .change(&mut |config| config.delete(name, mode).map(drop));
+    content_result(state, result)
+}
+
+/// Delete several names, newline-separated in the `names` field, as one
+/// change.
+async fn delete_many(
+    State(state): S,
+    q: Query<DeleteQuery>,
+    axum::extract::Form(form): Form,
+) -> Html<String> {
+    let mode = parse_delete_mode(&q);
+    let names: Vec<&str> = form
+        .get("names")
+        .map(|names| names.lines().filter(|n| !n.is_empty()).collect())
+        .unwrap_or_default();
+    let result = state.storage().change(&mut |config| {
+        for name in &names {
+            // An earlier cascade may have removed it already
+            if config.contains(name) {
+                config.delete(name, mode)?;
             }
-            let mut config = config;
-            config.groups.insert(name, entries);
-            state.save_config(&config);
         }
-    }
-    content_ok(&state)
+        Ok(())
+    });
+    content_result(&state, result)
 }
 
-async fn delete_link(State(state): S, Path(name): Path<String>) -> Html<String> {
-    let mut config = state.load_config();
-    config.links.remove(&name);
-    state.save_config(&config);
-    content_ok(&state)
+async fn delete_link(
+    State(state): S,
+    Path(name): Path<String>,
+    q: Query<DeleteQuery>,
+) -> Html<String> {
+    delete_name(&state, &name, &q)
 }
 
-async fn delete_alias(State(state): S, Path(name): Path<String>) -> Html<String> {
-    let mut config = state.load_config();
-    config.aliases.remove(&name);
-    state.save_config(&config);
-    content_ok(&state)
+async fn delete_alias(
+    State(state): S,
+    Path(name): Path<String>,
+    q: Query<DeleteQuery>,
+) -> Html<String> {
+    delete_name(&state, &name, &q)
+}
+
+async fn delete_group(
+    State(state): S,
+    Path(name): Path<String>,
+    q: Query<DeleteQuery>,
+) -> Html<String> {
+    delete_name(&state, &name, &q)
+}
+
+// -- History handlers --------------------------------------------------------
+
+async fn undo(State(state): S) -> Html<String> {
+    let result = state.storage().undo();
+    match result {
+        Ok(Some(summary)) => {
+            let Html(html) = content_ok(&state);
+            Html(html + &toast(&format!("undid: {summary}"), "redo"))
+        }
+        Ok(None) => content_err(&state, "nothing to undo"),
+        Err(e) => content_err(&state, &format!("{e:#}")),
+    }
 }
 
-async fn delete_group(State(state): S, Path(name): Path<String>) -> Html<String> {
-    let mut config = state.load_config();
-    config.groups.remove(&name);
-    state.save_config(&config);
-    content_ok(&state)
+async fn redo(State(state): S) -> Html<String> {
+    let result = state.storage().redo();
+    match result {
+        Ok(Some(summary)) => {
+            let Html(html) = content_ok(&state);
+            Html(html + &toast(&format!("redid: {summary}"), "undo"))
+        }
+        Ok(None) => content_err(&state, "nothing to redo"),
+        Err(e) => content_err(&state, &format!("{e:#}")),
+    }
 }
 
 // -- Edit handlers -----------------------------------------------------------
@@ -717,26 +902,24 @@ async fn edit_link(
     Path(name): Path<String>,
     axum::extract::Form(form): Form,
 ) -> Html<String> {
-    let mut config = state.load_config();
     let new_name = form.get("new_name").filter(|s| !s.is_empty());
     let new_url = form.get("new_url").filter(|s| !s.is_empty());
 
-    if let Some(new_url) = new_url {
-        if let Some(url) = config.links.get_mut(&name) {
-            *url = new_url.clone();
+    // One save for both changes, so they're undone together and neither
+    // brings back an entry deleted in the meantime
+    let result = state.storage().change(&mut |config| {
+        if !config.links.contains_key(&name) {
+            anyhow::bail!("link '{name}' not found");
         }
-    }
-
-    if let Some(new_name) = new_name {
-        if new_name != &name {
-            if let Err(e) = config.rename_link(&name, new_name) {
-                return content_err(&state, &e.to_string());
-            }
+        if let Some(new_url) = new_url {
+            config.add_link(&name, new_url)?;
         }
-    }
-
-    state.save_config(&config);
-    content_ok(&state)
+        if let Some(new_name) = new_name.filter(|n| **n != name) {
+            config.rename_link(&name, new_name)?;
+        }
+        Ok(())
+    });
+    content_result(&state, result)
 }
 
 async fn edit_alias(
@@ -744,29 +927,22 @@ async fn edit_alias(
     Path(name): Path<String>,
     axum::extract::Form(form): Form,
 ) -> Html<String> {
-    let mut config = state.load_config();
     let new_name = form.get("new_name").filter(|s| !s.is_empty());
     let new_target = form.get("new_target").filter(|s| !s.is_empty());
 
-    if let Some(new_target) = new_target {
-        if !config.links.contains_key(new_target) {
-            return content_err(&state, &strings::err_alias_target_missing(new_target));
+    let result = state.storage().change(&mut |config| {
+        if !config.aliases.contains_key(&name) {
+            anyhow::bail!("alias '{name}' not found");
         }
-        if let Some(target) = config.aliases.get_mut(&name) {
-            *target = new_target.clone();
+        if let Some(new_target) = new_target {
+            config.add_alias(&name, new_target)?;
         }
-    }
-
-    if let Some(new_name) = new_name {
-        if new_name != &name {
-            if let Err(e) = config.rename_alias(&name, new_name) {
-                return content_err(&state, &e.to_string());
-            }
+        if let Some(new_name) = new_name.filter(|n| **n != name) {
+            config.rename_alias(&name, new_name)?;
         }
-    }
-
-    state.save_config(&config);
-    content_ok(&state)
+        Ok(())
+    });
+    content_result(&state, result)
 }
 
 async fn edit_group(
@@ -774,46 +950,27 @@ async fn edit_group(
     Path(name): Path<String>,
     axum::extract::Form(form): Form,
 ) -> Html<String> {
-    let mut config = state.load_config();
     let new_name = form.get("new_name").filter(|s| !s.is_empty());
     let new_entries = form.get("new_entries").filter(|s| !s.is_empty());
 
-    if let Some(new_entries) = new_entries {
-        let entries: Vec<String> = new_entries
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-        let missing: Vec<&str> = entries
-            .iter()
-            .filter(|e| {
-                !config.links.contains_key(e.as_str()) && !config.aliases.contains_key(e.as_str())
-            })
-            .map(String::as_str)
-            .collect();
-        if !missing.is_empty() {
-            return content_err(&state, &strings::err_group_entries_missing(&missing));
+    let result = state.storage().change(&mut |config| {
+        if !config.groups.contains_key(&name) {
+            anyhow::bail!("group '{name}' not found");
         }
-        if let Some(existing) = config.groups.get_mut(&name) {
-            *existing = entries;
+        if let Some(new_entries) = new_entries {
+            config.add_group(&name, parse_entries(new_entries))?;
         }
-    }
-
-    if let Some(new_name) = new_name {
-        if new_name != &name {
-            if let Some(entries) = config.groups.remove(&name) {
-                config.groups.insert(new_name.clone(), entries);
-            }
+        if let Some(new_name) = new_name.filter(|n| **n != name) {
+            config.rename_group(&name, new_name)?;
         }
-    }
-
-    state.save_config(&config);
-    content_ok(&state)
+        Ok(())
+    });
+    content_result(&state, result)
 }
 
 // -- Server ------------------------------------------------------------------
 
-fn create_router(storage: Box<dyn Storage>) -> Router {
+fn create_router(storage: JournaledStorage) -> Router {
     let state = Arc::new(AppState {
         storage: Mutex::new(storage),
     });
@@ -824,16 +981,19 @@ fn create_router(storage: Box<dyn Storage>) -> Router {
         .route("/add/link", post(add_link))
         .route("/add/alias", post(add_alias))
         .route("/add/group", post(add_group))
+        .route("/delete", post(delete_many))
         .route("/delete/link/{name}", post(delete_link))
         .route("/delete/alias/{name}", post(delete_alias))
         .route("/delete/group/{name}", post(delete_group))
         .route("/edit/link/{name}", post(edit_link))
         .route("/edit/alias/{name}", post(edit_alias))
         .route("/edit/group/{name}", post(edit_group))
+        .route("/undo", post(undo))
+        .route("/redo", post(redo))
         .with_state(state)
 }
 
-pub fn run(storage: Box<dyn Storage>) -> anyhow::Result<()> {
+pub fn run(storage: JournaledStorage) -> anyhow::Result<()> {
     let rt = tokio::runtime::Runtime::new()?;
     rt.block_on(async {
         let port: u16 = 1414;